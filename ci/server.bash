@@ -10,6 +10,8 @@ set -x
 
 export RUSTFLAGS="-D warnings"
 
+python3 ci/test_server.py &
+
 git clone --depth 1 https://github.com/najamelan/ws_stream_tungstenite
 cd ws_stream_tungstenite
 cargo build --example echo --release
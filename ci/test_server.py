@@ -0,0 +1,135 @@
+#!/usr/bin/env python3
+
+# WebSocket servers that misbehave on purpose, for the tests that need more than an echo server. Only uses the
+# standard library, so it runs wherever python 3.7+ is installed:
+#
+#   python3 ci/test_server.py
+#
+# Every behavior listens on its own port on 127.0.0.1:
+#
+# 3313 hang  : accepts the TCP connection but never answers the opening handshake.
+# 3314 silent: completes the handshake, then never sends anything, not even the answer to a close frame.
+# 3315 close : completes the handshake, then closes with the code in the path, eg. ws://127.0.0.1:3315/4001.
+# 3316 once  : accepts the first connection to a path, then drops it and refuses every later one to that path.
+#
+import asyncio, base64, hashlib
+
+
+HOST = "127.0.0.1"
+GUID = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11"
+
+
+# Read the opening handshake. Returns the path that was requested and the key of the client.
+#
+async def request( reader ):
+
+	request = await reader.readuntil( b"\r\n\r\n" )
+	lines   = request.decode().split( "\r\n" )
+	path    = lines[0].split( " " )[1]
+	headers = dict( line.split( ": ", 1 ) for line in lines[1:] if ": " in line )
+	key     = next( value for name, value in headers.items() if name.lower() == "sec-websocket-key" )
+
+	return path, key
+
+
+# Accept the opening handshake of the client with this key.
+#
+async def accept( writer, key ):
+
+	accept = base64.b64encode( hashlib.sha1( ( key + GUID ).encode() ).digest() ).decode()
+
+	writer.write( "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n"
+	              "Sec-WebSocket-Accept: {}\r\n\r\n".format( accept ).encode() )
+
+	await writer.drain()
+
+
+# Read the opening handshake and accept it. Returns the path that was requested.
+#
+async def handshake( reader, writer ):
+
+	path, key = await request( reader )
+	await accept( writer, key )
+
+	return path
+
+
+# Accept the connection and read whatever comes in, without ever answering.
+#
+async def hang( reader, writer ):
+
+	while await reader.read( 4096 ):
+		pass
+
+	writer.close()
+
+
+# Accept the connection, then ignore everything the client sends, like a server that died without the TCP
+# connection noticing.
+#
+async def silent( reader, writer ):
+
+	await handshake( reader, writer )
+	await hang( reader, writer )
+
+
+# Accept the connection, then close it with the code given as path, like a server rejecting the credentials
+# of the client.
+#
+async def close( reader, writer ):
+
+	path = await handshake( reader, writer )
+	code = int( path.split( "?" )[0].strip( "/" ) )
+
+	writer.write( bytes([ 0x88, 2 ]) + code.to_bytes( 2, "big" ) )
+	await writer.drain()
+
+	# Wait for the close frame of the client before closing the TCP connection, so the close is clean.
+	#
+	await reader.read( 4096 )
+	writer.close()
+
+
+# The paths that already had their connection.
+#
+SEEN = set()
+
+
+# Accept the first connection to a path and drop it shortly after, like a server that crashed. Later connections
+# to that path are dropped before answering the handshake, like a server that stays down. Use a new path for every
+# test run.
+#
+async def once( reader, writer ):
+
+	path, key = await request( reader )
+
+	if path not in SEEN:
+
+		SEEN.add( path )
+		await accept( writer, key )
+		await asyncio.sleep( 0.1 )
+
+	writer.close()
+
+
+SERVERS = \
+{
+	3313: hang  ,
+	3314: silent,
+	3315: close ,
+	3316: once  ,
+}
+
+
+async def main():
+
+	servers = [ await asyncio.start_server( handler, HOST, port ) for port, handler in SERVERS.items() ]
+
+	for port, handler in SERVERS.items():
+		print( "{}: listening on ws://{}:{}/".format( handler.__name__, HOST, port ), flush = True )
+
+	await asyncio.gather( *( server.serve_forever() for server in servers ) )
+
+
+if __name__ == "__main__":
+	asyncio.run( main() )
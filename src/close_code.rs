@@ -0,0 +1,217 @@
+use crate::{ import::*, WsErr };
+
+
+/// The status code of a closed connection, as registered in
+/// [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4.1) and the
+/// [IANA registry](https://www.iana.org/assignments/websocket/websocket.xhtml#close-code-number).
+///
+/// Converting from a `u16` never fails. Codes that have no variant of their own end up in [CloseCode::Other].
+/// Browsers only allow sending [CloseCode::Normal], [CloseCode::Library] and [CloseCode::Application]. See
+/// [CloseCode::is_allowed].
+///
+/// Equality and hashing compare the numeric code, not the variant, so a variant holding a code outside of its
+/// range still equals the code received from the browser.
+///
+/// See: [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/CloseEvent/code).
+///
+/// ```
+/// use ws_stream_wasm::CloseCode;
+///
+/// assert_eq!( CloseCode::Abnormal           , CloseCode::from( 1006 )        );
+/// assert_eq!( CloseCode::Application( 4000 ), CloseCode::from( 4000 )        );
+/// assert_eq!( 1000                          , u16::from( CloseCode::Normal ) );
+/// assert_eq!( CloseCode::Library    ( 4001 ), CloseCode::from( 4001 )        );
+///
+/// assert!(  CloseCode::Library( 3000 ).is_allowed() );
+/// assert!( !CloseCode::Away           .is_allowed() );
+/// ```
+//
+#[ derive( Debug, Clone, Copy ) ]
+//
+pub enum CloseCode
+{
+	/// 1000: The purpose for which the connection was established has been fulfilled.
+	//
+	Normal,
+
+	/// 1001: An endpoint is going away, eg. a server going down or a browser navigating away from a page.
+	//
+	Away,
+
+	/// 1002: An endpoint is terminating the connection due to a protocol error.
+	//
+	Protocol,
+
+	/// 1003: An endpoint received a type of data it can't accept.
+	//
+	Unsupported,
+
+	/// 1005: No status code was present in the close frame. Never sent over the wire.
+	//
+	Status,
+
+	/// 1006: The connection was closed without a close frame, eg. because the network failed. Never sent over the wire.
+	//
+	Abnormal,
+
+	/// 1007: An endpoint received data in a message that is not consistent with the type of the message.
+	//
+	Invalid,
+
+	/// 1008: An endpoint received a message that violates its policy.
+	//
+	Policy,
+
+	/// 1009: An endpoint received a message that is too big to process.
+	//
+	Size,
+
+	/// 1010: The client expected the server to negotiate one or more extensions, but it didn't.
+	//
+	Extension,
+
+	/// 1011: The server encountered an unexpected condition that prevented it from fulfilling the request.
+	//
+	Error,
+
+	/// 1012: The server is restarting.
+	//
+	Restart,
+
+	/// 1013: The server is overloaded, try again later.
+	//
+	Again,
+
+	/// 1015: The TLS handshake failed. Never sent over the wire.
+	//
+	Tls,
+
+	/// 3000-3999: Codes reserved for libraries, frameworks and applications, registered with IANA.
+	//
+	Library( u16 ),
+
+	/// 4000-4999: Codes for private use by applications.
+	//
+	Application( u16 ),
+
+	/// Any other code. These are either reserved or not valid at all.
+	//
+	Other( u16 ),
+}
+
+
+
+impl CloseCode
+{
+	/// Whether browsers allow sending this code when closing a connection. That is [CloseCode::Normal] and codes
+	/// in the range 3000-4999.
+	//
+	pub fn is_allowed( self ) -> bool
+	{
+		let code = u16::from( self );
+
+		code == 1000 || (3000..=4999).contains( &code )
+	}
+
+
+	/// Returns [WsErr::InvalidCloseCode] if browsers don't allow sending this code. See [CloseCode::is_allowed].
+	//
+	pub(crate) fn validate( self ) -> Result< Self, WsErr >
+	{
+		match self.is_allowed()
+		{
+			true  => Ok ( self                                                   ),
+			false => Err( WsErr::InvalidCloseCode{ supplied: u16::from( self ) } ),
+		}
+	}
+}
+
+
+
+impl From<u16> for CloseCode
+{
+	fn from( code: u16 ) -> Self
+	{
+		match code
+		{
+			1000         => CloseCode::Normal              ,
+			1001         => CloseCode::Away                ,
+			1002         => CloseCode::Protocol            ,
+			1003         => CloseCode::Unsupported         ,
+			1005         => CloseCode::Status              ,
+			1006         => CloseCode::Abnormal            ,
+			1007         => CloseCode::Invalid             ,
+			1008         => CloseCode::Policy              ,
+			1009         => CloseCode::Size                ,
+			1010         => CloseCode::Extension           ,
+			1011         => CloseCode::Error               ,
+			1012         => CloseCode::Restart             ,
+			1013         => CloseCode::Again               ,
+			1015         => CloseCode::Tls                 ,
+			3000..=3999  => CloseCode::Library    ( code ) ,
+			4000..=4999  => CloseCode::Application( code ) ,
+			_            => CloseCode::Other      ( code ) ,
+		}
+	}
+}
+
+
+
+impl From<CloseCode> for u16
+{
+	fn from( code: CloseCode ) -> Self
+	{
+		match code
+		{
+			CloseCode::Normal              => 1000 ,
+			CloseCode::Away                => 1001 ,
+			CloseCode::Protocol            => 1002 ,
+			CloseCode::Unsupported         => 1003 ,
+			CloseCode::Status              => 1005 ,
+			CloseCode::Abnormal            => 1006 ,
+			CloseCode::Invalid             => 1007 ,
+			CloseCode::Policy              => 1008 ,
+			CloseCode::Size                => 1009 ,
+			CloseCode::Extension           => 1010 ,
+			CloseCode::Error               => 1011 ,
+			CloseCode::Restart             => 1012 ,
+			CloseCode::Again               => 1013 ,
+			CloseCode::Tls                 => 1015 ,
+			CloseCode::Library    ( code ) => code ,
+			CloseCode::Application( code ) => code ,
+			CloseCode::Other      ( code ) => code ,
+		}
+	}
+}
+
+
+
+impl PartialEq for CloseCode
+{
+	fn eq( &self, other: &Self ) -> bool
+	{
+		u16::from( *self ) == u16::from( *other )
+	}
+}
+
+
+impl Eq for CloseCode {}
+
+
+impl Hash for CloseCode
+{
+	fn hash<H: Hasher>( &self, state: &mut H )
+	{
+		u16::from( *self ).hash( state );
+	}
+}
+
+
+
+impl fmt::Display for CloseCode
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "{}", u16::from( *self ) )
+	}
+}
@@ -0,0 +1,219 @@
+use crate::{ import::*, WsErr };
+
+
+/// The url and sub-protocols of a connection attempt, handed to the provider set with
+/// [WsConfig::connect_params](crate::WsConfig::connect_params) before every `WebSocket` is created. Browsers can't
+/// set headers on the upgrade request, so this is where short lived credentials go, in the query string or in a
+/// sub-protocol.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub struct ConnectParams
+{
+	url      : String      ,
+	protocols: Vec<String> ,
+	refresh  : bool        ,
+}
+
+
+
+impl ConnectParams
+{
+	pub(crate) fn new( url: String, protocols: Vec<String>, refresh: bool ) -> Self
+	{
+		Self { url, protocols, refresh }
+	}
+
+
+	pub(crate) fn into_parts( self ) -> ( String, Vec<String> )
+	{
+		( self.url, self.protocols )
+	}
+
+
+	/// The url to connect to. Relative and `http(s)` urls have already been resolved to a `ws(s)` url.
+	//
+	pub fn url( &self ) -> &str
+	{
+		&self.url
+	}
+
+
+	/// Replace the url to connect to.
+	//
+	pub fn set_url( &mut self, url: impl AsRef<str> )
+	{
+		self.url = url.as_ref().to_string();
+	}
+
+
+	/// Set a parameter in the query string of the url, replacing it if it is already there. Does nothing if
+	/// the url was replaced with one that can't be parsed, in which case connecting fails with
+	/// [WsErr::InvalidUrl].
+	//
+	pub fn set_query( &mut self, name: &str, value: &str )
+	{
+		if let Ok( url ) = Url::new( &self.url )
+		{
+			url.search_params().set( name, value );
+			self.url = url.href();
+		}
+	}
+
+
+	/// The sub-protocols to offer the server.
+	//
+	pub fn protocols( &self ) -> &[String]
+	{
+		&self.protocols
+	}
+
+
+	/// Replace the sub-protocols to offer the server.
+	//
+	pub fn set_protocols( &mut self, protocols: impl IntoIterator< Item = impl Into<String> > )
+	{
+		self.protocols = protocols.into_iter().map( Into::into ).collect();
+	}
+
+
+	/// Whether the server rejected the credentials of the previous attempt, by closing with one of the codes
+	/// given to [WsConfig::credentials_rejected_on](crate::WsConfig::credentials_rejected_on). Cached credentials
+	/// should not be used again in that case.
+	//
+	pub fn refresh( &self ) -> bool
+	{
+		self.refresh
+	}
+}
+
+
+
+/// The error a provider set with [WsConfig::connect_params](crate::WsConfig::connect_params) can return.
+//
+pub type BoxError = Box< dyn std::error::Error + Send + Sync >;
+
+
+
+/// The error returned by the provider set with [WsConfig::connect_params](crate::WsConfig::connect_params), found
+/// in [WsErr::ConnectParams]. Use [ConnectParamsError::downcast_ref] to get back the error type of the provider, eg.
+/// to tell an expired login from a network failure.
+///
+/// [WsErr] can be cloned and compared, so the error is shared: two of these are equal when they hold the same
+/// error, not an equal one.
+//
+#[ derive( Clone ) ]
+//
+pub struct ConnectParamsError( Arc< dyn std::error::Error + Send + Sync > );
+
+
+
+impl ConnectParamsError
+{
+	/// The error of the provider.
+	//
+	pub fn get_ref( &self ) -> &( dyn std::error::Error + Send + Sync + 'static )
+	{
+		&*self.0
+	}
+
+
+	/// The error of the provider, if it is of type `E`.
+	//
+	pub fn downcast_ref<E: std::error::Error + 'static>( &self ) -> Option<&E>
+	{
+		self.0.downcast_ref()
+	}
+}
+
+
+
+impl From<BoxError> for ConnectParamsError
+{
+	fn from( error: BoxError ) -> Self
+	{
+		Self( Arc::from( error ) )
+	}
+}
+
+
+
+impl PartialEq for ConnectParamsError
+{
+	fn eq( &self, other: &Self ) -> bool
+	{
+		Arc::ptr_eq( &self.0, &other.0 )
+	}
+}
+
+impl Eq for ConnectParamsError {}
+
+
+
+impl fmt::Debug for ConnectParamsError
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		fmt::Debug::fmt( &self.0, f )
+	}
+}
+
+
+
+impl fmt::Display for ConnectParamsError
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		fmt::Display::fmt( &self.0, f )
+	}
+}
+
+
+
+impl std::error::Error for ConnectParamsError
+{
+	fn source( &self ) -> Option<&( dyn std::error::Error + 'static )>
+	{
+		self.0.source()
+	}
+}
+
+
+
+type ProvideFn = dyn Fn( ConnectParams ) -> Pin<Box< dyn Future< Output = Result<ConnectParams, BoxError> > >>;
+
+
+// The provider set with WsConfig::connect_params.
+//
+#[ derive( Clone ) ]
+//
+pub(crate) struct ConnectParamsProvider( Rc< ProvideFn > );
+
+
+
+impl ConnectParamsProvider
+{
+	pub(crate) fn new<F, Fut>( provider: F ) -> Self
+
+		where F  : Fn( ConnectParams ) -> Fut + 'static                          ,
+		      Fut: Future< Output = Result<ConnectParams, BoxError> > + 'static ,
+	{
+		Self( Rc::new( move |params| provider( params ).boxed_local() ) )
+	}
+
+
+	pub(crate) async fn provide( &self, params: ConnectParams ) -> Result<ConnectParams, WsErr>
+	{
+		(self.0)( params ).await.map_err( |error| WsErr::ConnectParams{ error: error.into() } )
+	}
+}
+
+
+
+impl fmt::Debug for ConnectParamsProvider
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "ConnectParamsProvider" )
+	}
+}
@@ -0,0 +1,108 @@
+use crate::{ import::* };
+
+
+#[ wasm_bindgen ]
+//
+extern "C"
+{
+	// We bind to the global functions rather than to `window.setTimeout`, so this also works in workers.
+	//
+	#[ wasm_bindgen( js_name = setTimeout ) ]
+	//
+	fn set_timeout( handler: &Function, timeout: i32 ) -> i32;
+
+	#[ wasm_bindgen( js_name = clearTimeout ) ]
+	//
+	fn clear_timeout( handle: i32 );
+}
+
+
+
+/// A future that resolves once the given duration has elapsed. It is based on the global `setTimeout`
+/// function, so it works in both window and worker contexts and doesn't depend on any executor.
+///
+/// When dropped before firing, the timeout is cleared.
+//
+pub(crate) struct Delay
+{
+	handle  : i32                            ,
+	fired   : Rc<Cell<bool>>                 ,
+	waker   : Rc<RefCell< Option<Waker> >>   ,
+	_closure: Closure< dyn FnMut() >         ,
+}
+
+
+
+impl Delay
+{
+	/// Start the timer. Durations longer than what `setTimeout` accepts are clamped.
+	//
+	pub(crate) fn new( duration: Duration ) -> Self
+	{
+		let fired = Rc::new( Cell::new( false ) );
+		let waker = Rc::new( RefCell::new( None::<Waker> ) );
+
+		let f2 = fired.clone();
+		let w2 = waker.clone();
+
+		#[ allow( trivial_casts ) ]
+		//
+		let closure = Closure::wrap( Box::new( move ||
+		{
+			f2.set( true );
+
+			if let Some(w) = w2.borrow_mut().take()
+			{
+				w.wake()
+			}
+
+		}) as Box< dyn FnMut() > );
+
+		let millis = duration.as_millis().min( i32::MAX as u128 ) as i32;
+		let handle = set_timeout( closure.as_ref().unchecked_ref(), millis );
+
+		Self { handle, fired, waker, _closure: closure }
+	}
+}
+
+
+
+impl Future for Delay
+{
+	type Output = ();
+
+	fn poll( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		if self.fired.get()
+		{
+			return Poll::Ready(());
+		}
+
+		*self.waker.borrow_mut() = Some( cx.waker().clone() );
+
+		Poll::Pending
+	}
+}
+
+
+
+impl Drop for Delay
+{
+	fn drop( &mut self )
+	{
+		if !self.fired.get()
+		{
+			clear_timeout( self.handle );
+		}
+	}
+}
+
+
+
+impl fmt::Debug for Delay
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "Delay {{ handle: {}, fired: {} }}", self.handle, self.fired.get() )
+	}
+}
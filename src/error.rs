@@ -1,6 +1,6 @@
 //! Crate specific errors.
 //
-use crate::{ import::*, CloseEvent };
+use crate::{ import::*, CloseEvent, ConnectParamsError };
 
 
 /// The error type for errors happening in `ws_stream_wasm`.
@@ -30,15 +30,90 @@ pub enum WsErr
 	/// An invalid URL was given to [WsMeta::connect](crate::WsMeta::connect), please see:
 	/// [HTML Living Standard](https://html.spec.whatwg.org/multipage/web-sockets.html#dom-websocket).
 	//
-	#[ error( "An invalid URL was given to the connect method: {supplied}" ) ]
+	#[ error( "An invalid URL was given to the connect method: {supplied}, {reason}" ) ]
 	//
 	InvalidUrl
+	{
+		/// The user supplied value that is invalid.
+		//
+		supplied: String,
+
+		/// What is wrong with it.
+		//
+		reason: UrlError,
+	},
+
+	/// A sub-protocol given to [WsMeta::connect](crate::WsMeta::connect) is not a valid token or occurs more
+	/// than once. See [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-4.1).
+	//
+	#[ error( "An invalid sub-protocol was given to the connect method: {supplied}" ) ]
+	//
+	InvalidProtocol
 	{
 		/// The user supplied value that is invalid.
 		//
 		supplied: String
 	},
 
+	/// The server accepted the connection without selecting any of the sub-protocols offered, whilst
+	/// [WsConfig::require_protocol](crate::WsConfig::require_protocol) was set. The connection has been closed.
+	//
+	#[ error( "The server didn't select any of the offered sub-protocols." ) ]
+	//
+	ProtocolNotNegotiated,
+
+	/// [WsConfig::require_protocol](crate::WsConfig::require_protocol) was set without offering any sub-protocol,
+	/// so the server could never select one.
+	//
+	#[ error( "A sub-protocol is required, but none was offered." ) ]
+	//
+	NoProtocolOffered,
+
+	/// The channel given to [WsConfig::observer_channel](crate::WsConfig::observer_channel) is bounded to 0 events.
+	//
+	#[ error( "The minimum size of a bounded observer channel is 1." ) ]
+	//
+	InvalidObserverChannel,
+
+	/// The provider set with [WsConfig::connect_params](crate::WsConfig::connect_params) returned an error.
+	//
+	#[ error( "Could not obtain the parameters for the connection: {error}" ) ]
+	//
+	ConnectParams
+	{
+		/// The error returned by the provider.
+		//
+		#[ source ] error: ConnectParamsError
+	},
+
+	/// The browser refused to make the connection, eg. a `ws://` connection from a page loaded over `https://`
+	/// (mixed content), a url not allowed by the `connect-src` directive of the Content Security Policy or a
+	/// port the browser blocks.
+	//
+	#[ error( "The browser refused to connect for security reasons: {message}" ) ]
+	//
+	SecurityError
+	{
+		/// The message of the `SecurityError` thrown by the browser.
+		//
+		message: String
+	},
+
+	/// The `WebSocket` constructor threw an exception that doesn't correspond to any of the other variants.
+	//
+	#[ error( "The browser threw a {name} when creating the connection: {message}" ) ]
+	//
+	Dom
+	{
+		/// The name of the exception, eg. `SyntaxError`.
+		//
+		name: String,
+
+		/// The message of the exception.
+		//
+		message: String,
+	},
+
 	/// An invalid close code was given to a close method. For valid close codes, please see:
 	/// [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/CloseEvent#Status_codes).
 	//
@@ -72,6 +147,55 @@ pub enum WsErr
 	},
 
 
+	/// The connection wasn't established within the timeout given to
+	/// [WsMeta::connect_timeout](crate::WsMeta::connect_timeout).
+	//
+	#[ error( "The connection wasn't established within the timeout." ) ]
+	//
+	ConnectTimeout,
+
+
+	/// None of the endpoints given to [Failover](crate::Failover) accepted the connection.
+	//
+	#[ error( "Could not connect to any of the endpoints: {errors:?}" ) ]
+	//
+	AllEndpointsFailed
+	{
+		/// The url of each endpoint with the error connecting to it, in the order the endpoints were given.
+		//
+		errors: Vec<( String, WsErr )>
+	},
+
+
+	/// The connection closed without a closing handshake and it wasn't closed from this side, eg. because the
+	/// network failed. Only returned as the last item of [WsStream::try_messages](crate::WsStream::try_messages).
+	//
+	#[ error( "The connection was lost. CloseEvent: {event:?}" ) ]
+	//
+	ConnectionLost
+	{
+		/// The close event reported by the browser.
+		//
+		event: CloseEvent
+	},
+
+
+	/// A message was received while the receive buffer of [WsStream](crate::WsStream) was full. The message
+	/// has been dropped. Only emitted with [OverflowPolicy::Error](crate::OverflowPolicy::Error).
+	//
+	#[ error( "The receive queue is full, an incoming message was dropped." ) ]
+	//
+	ReceiveQueueFull,
+
+
+	/// A message was sent while the connection was connecting, but the outbox configured with
+	/// [WsConfig::outbox](crate::WsConfig::outbox) is full.
+	//
+	#[ error( "The outbox is full, wait for the connection to open before sending more messages." ) ]
+	//
+	OutboxFull,
+
+
 	/// When converting the JavaScript Message into a WsMessage, it's possible that
 	/// a String message doesn't convert correctly as Js does not guarantee that
 	/// strings are valid Unicode. Happens in `impl TryFrom< MessageEvent > for WsMessage`.
@@ -87,7 +211,8 @@ pub enum WsErr
 	/// [`WsMeta::connect`](crate::WsMeta::connect), you have to make sure to set the binary
 	/// type of the connection to `ArrayBuffer`.
 	///
-	/// Happens in `impl TryFrom< MessageEvent > for WsMessage`.
+	/// Happens in `impl TryFrom< MessageEvent > for WsMessage`. Also emitted as an event
+	/// when the browser fails to read a Blob message received by [WsStream](crate::WsStream).
 	//
 	#[ error( "Received a Blob message that couldn't converted." ) ]
 	//
@@ -106,3 +231,42 @@ pub enum WsErr
 }
 
 
+
+
+
+
+/// What is wrong with the url in [WsErr::InvalidUrl].
+//
+#[ derive( Debug, Error, Clone, PartialEq, Eq ) ] #[ non_exhaustive ]
+//
+pub enum UrlError
+{
+	/// The url can't be parsed, or it's relative and there is no document or worker location to resolve it against.
+	//
+	#[ error( "it can't be parsed or resolved against the current location" ) ]
+	//
+	Unparsable,
+
+	/// Only `ws`, `wss`, `http` and `https` urls can be used to connect.
+	//
+	#[ error( "the scheme {scheme:?} isn't supported" ) ]
+	//
+	UnsupportedScheme
+	{
+		/// The scheme of the url, without the colon.
+		//
+		scheme: String
+	},
+
+	/// The url has no host.
+	//
+	#[ error( "it has no host" ) ]
+	//
+	MissingHost,
+
+	/// Websocket urls can't have a fragment.
+	//
+	#[ error( "it has a fragment" ) ]
+	//
+	Fragment,
+}
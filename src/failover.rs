@@ -0,0 +1,238 @@
+use crate::{ import::*, delay::Delay, WsConfig, WsErr, WsMeta, WsStream };
+
+
+/// A list of equivalent endpoints to connect to, eg. several gateways of the same service. [Failover::connect]
+/// tries them until one accepts the connection and tells which one did.
+///
+/// By default the endpoints are tried one after the other in the order they were given. With
+/// [Failover::parallel], several attempts run at the same time: a new attempt is started every `stagger`, and right
+/// away for every attempt that fails, until one succeeds. The attempts that lost are cancelled and their connections
+/// closed.
+///
+/// ```
+/// use { ws_stream_wasm::*, std::time::Duration };
+///
+/// let failover = Failover::new( vec![ "wss://eu.example.com", "wss://us.example.com" ] )
+///
+///    .endpoint( "wss://backup.example.com", 1     )
+///    .order   ( FailoverOrder::Weighted           )
+///    .parallel( 2, Duration::from_millis( 250 )   )
+/// ;
+/// ```
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct Failover
+{
+	endpoints: Vec<( String, u32 )> ,
+	order    : FailoverOrder        ,
+	parallel : usize                ,
+	stagger  : Duration             ,
+}
+
+
+
+/// The order in which [Failover] tries its endpoints.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum FailoverOrder
+{
+	/// In the order they were given.
+	//
+	InOrder,
+
+	/// In a random order, so clients spread over the endpoints.
+	//
+	Shuffled,
+
+	/// In a random order where endpoints with a higher weight are more likely to come first.
+	//
+	Weighted,
+}
+
+
+
+impl Failover
+{
+	/// Create a list of endpoints with a weight of 1 each, tried in order.
+	//
+	pub fn new( urls: impl IntoIterator< Item = impl AsRef<str> > ) -> Self
+	{
+		Self
+		{
+			endpoints: urls.into_iter().map( |url| ( url.as_ref().to_string(), 1 ) ).collect() ,
+			order    : FailoverOrder::InOrder                                                   ,
+			parallel : 1                                                                        ,
+			stagger  : Duration::ZERO                                                           ,
+		}
+	}
+
+
+	/// Add an endpoint with a weight, used with [FailoverOrder::Weighted]. An endpoint with weight 0 is only tried
+	/// after all others.
+	//
+	pub fn endpoint( mut self, url: impl AsRef<str>, weight: u32 ) -> Self
+	{
+		self.endpoints.push(( url.as_ref().to_string(), weight ));
+		self
+	}
+
+
+	/// The order in which to try the endpoints.
+	//
+	pub fn order( mut self, order: FailoverOrder ) -> Self
+	{
+		self.order = order;
+		self
+	}
+
+
+	/// Run up to `attempts` connection attempts at the same time, starting a new one every `stagger` while none
+	/// succeeded. Values below 1 are treated as 1.
+	//
+	pub fn parallel( mut self, attempts: usize, stagger: Duration ) -> Self
+	{
+		self.parallel = attempts.max( 1 );
+		self.stagger  = stagger;
+		self
+	}
+
+
+	/// Connect with the default options. See [Failover::connect_with].
+	//
+	pub async fn connect( &self ) -> Result< (WsMeta, WsStream, usize), WsErr >
+	{
+		self.connect_with( WsConfig::new( "" ) ).await
+	}
+
+
+	/// Connect to the first endpoint that accepts the connection, with the options of `config`. Its url is
+	/// replaced by the one of each endpoint.
+	///
+	/// On success, this also returns the index of the endpoint that was connected to, in the order the endpoints
+	/// were given. When all of them fail, [WsErr::AllEndpointsFailed] holds the error of each endpoint. Errors
+	/// in `config` itself are returned right away, as they would be the same for every endpoint.
+	//
+	pub async fn connect_with( &self, config: WsConfig ) -> Result< (WsMeta, WsStream, usize), WsErr >
+	{
+		config.validate()?;
+
+		let mut queue    = self.attempt_order();
+		let mut attempts = Vec::new();
+		let mut errors   = vec![ None; self.endpoints.len() ];
+		let mut stagger  = None::<Delay>;
+		let mut failed   = 0_usize;
+		let mut refresh  = false;
+
+		future::poll_fn( |cx| loop
+		{
+			// Start the next attempt when nothing is running, to replace one that failed or once the stagger delay
+			// has passed.
+			//
+			if attempts.len() < self.parallel && !queue.is_empty()
+			{
+				let stagger_passed = match &mut stagger
+				{
+					Some( delay ) => Pin::new( delay ).poll( cx ).is_ready(),
+					None          => true                                  ,
+				};
+
+				let due = attempts.is_empty() || failed > 0 || stagger_passed;
+
+				if due
+				{
+					let i          = queue.pop_front().expect_throw( "queue is not empty" );
+					let mut config = config.clone();
+
+					config.url            = self.endpoints[i].0.clone();
+					config.refresh_params = refresh;
+					config.cancel_quietly = true;
+
+					attempts.push(( i, config.connect().boxed_local() ));
+
+					stagger = Some( Delay::new( self.stagger ) );
+					failed  = failed.saturating_sub( 1 );
+
+					continue;
+				}
+			}
+
+			let mut k = 0;
+
+			while k < attempts.len()
+			{
+				let i = attempts[k].0;
+
+				match attempts[k].1.as_mut().poll( cx )
+				{
+					Poll::Pending => k += 1,
+
+					// Dropping the other attempts closes their connections.
+					//
+					Poll::Ready( Ok(( meta, stream )) ) => return Poll::Ready( Ok(( meta, stream, i )) ),
+
+					Poll::Ready( Err(e) ) =>
+					{
+						log::warn!( "Failover: could not connect to {}: {}.", self.endpoints[i].0, e );
+
+						if let WsErr::ConnectionFailed{ event } = &e
+						{
+							refresh = refresh || config.rejects_credentials( event );
+						}
+
+						errors[i] = Some( e );
+						failed   += 1;
+
+						drop( attempts.remove( k ) );
+					}
+				}
+			}
+
+			if attempts.is_empty() && queue.is_empty()
+			{
+				let errors = self.endpoints.iter().zip( errors.iter_mut() )
+
+					.map( |((url, _), e)| ( url.clone(), e.take().expect_throw( "every endpoint was tried" ) ) )
+					.collect()
+				;
+
+				return Poll::Ready( Err( WsErr::AllEndpointsFailed{ errors } ) );
+			}
+
+			// After a failure, start the next attempts right away if there are any. Otherwise all attempts have
+			// been polled and will wake us up.
+			//
+			if failed == 0 || queue.is_empty()
+			{
+				return Poll::Pending;
+			}
+		}).await
+	}
+
+
+	// The indices of the endpoints in the order they should be tried.
+	//
+	fn attempt_order( &self ) -> VecDeque<usize>
+	{
+		let mut order: Vec<usize> = ( 0..self.endpoints.len() ).collect();
+
+		// Sort by a random key, highest first. For weighted order, u^(1/w) gives every endpoint a chance to come
+		// first proportional to its weight (Efraimidis and Spirakis).
+		//
+		let keys: Vec<f64> = self.endpoints.iter().map( |(_, weight)| match ( self.order, weight )
+		{
+			( FailoverOrder::InOrder , _ ) => 0.0                                                   ,
+			( FailoverOrder::Shuffled, _ ) => js_sys::Math::random()                                ,
+			( FailoverOrder::Weighted, 0 ) => -1.0                                                  ,
+			( FailoverOrder::Weighted, w ) => js_sys::Math::random().powf( 1.0 / f64::from( *w ) ) ,
+		})
+			.collect();
+
+		// The sort is stable, so InOrder keeps the order.
+		//
+		order.sort_by( |a, b| keys[*b].total_cmp( &keys[*a] ) );
+
+		order.into()
+	}
+}
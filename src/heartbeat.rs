@@ -0,0 +1,287 @@
+use crate::{ import::*, delay::Delay, ws_meta::force_closed, notifier::Notifier, CloseCode, CloseInitiator, JsMessage, WsEvent, WsMessage };
+
+
+/// Configuration for an application level keepalive, enabled with [WsConfig::heartbeat](crate::WsConfig::heartbeat).
+///
+/// Browsers don't expose WebSocket ping and pong frames, so a connection that silently died can stay
+/// [WsState::Open](crate::WsState::Open) for minutes. With a heartbeat, [WsStream](crate::WsStream) sends a ping
+/// message at every `interval`. The server is expected to answer with a pong message. Incoming messages recognized
+/// as pong are not passed on to the consumer of the stream.
+///
+/// Observers receive [WsEvent::Pong] with the measured round trip time for every pong. When no pong arrives within
+/// `timeout` after a ping, observers receive [WsEvent::HeartbeatTimeout] and the connection is closed with
+/// `close_code`.
+///
+/// The defaults are: an interval of 30 seconds, a timeout of 10 seconds, the text message `ping` as ping, the text
+/// message `pong` as pong and [CloseCode::Normal] as close code.
+///
+/// ```
+/// use { ws_stream_wasm::*, std::time::Duration };
+///
+/// let heartbeat = Heartbeat::default()
+///
+///    .interval( Duration::from_secs( 5 )       )
+///    .ping    ( WsMessage::Binary( vec![ 0 ] ) )
+///
+///    .is_pong( |msg| matches!( msg, JsMessage::Binary( bytes ) if bytes.len() == 1 && bytes.to_vec() == [ 1 ] ) )
+/// ;
+/// ```
+//
+#[ derive( Clone ) ]
+//
+pub struct Heartbeat
+{
+	interval  : Duration                           ,
+	timeout   : Duration                           ,
+	ping      : WsMessage                          ,
+	is_pong   : Rc< dyn Fn( &JsMessage ) -> bool > ,
+	close_code: CloseCode                          ,
+}
+
+
+
+impl Default for Heartbeat
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			interval  : Duration::from_secs( 30 )             ,
+			timeout   : Duration::from_secs( 10 )             ,
+			ping      : WsMessage::Text( "ping".to_string() ) ,
+			close_code: CloseCode::Normal                     ,
+
+			is_pong: Rc::new( |msg: &JsMessage| matches!( msg, JsMessage::Text( text ) if text == "pong" ) ),
+		}
+	}
+}
+
+
+
+impl Heartbeat
+{
+	/// How long to wait after a pong, or after the connection opened, before sending the next ping.
+	//
+	pub fn interval( mut self, interval: Duration ) -> Self
+	{
+		self.interval = interval;
+		self
+	}
+
+
+	/// How long to wait for a pong after sending a ping before considering the connection dead.
+	//
+	pub fn timeout( mut self, timeout: Duration ) -> Self
+	{
+		self.timeout = timeout;
+		self
+	}
+
+
+	/// The message to send as ping.
+	//
+	pub fn ping( mut self, ping: WsMessage ) -> Self
+	{
+		self.ping = ping;
+		self
+	}
+
+
+	/// A predicate recognizing the answer of the server to a ping. It is called for every incoming message, before
+	/// binary data is copied into WASM memory. To keep that cheap on connections that receive a lot of binary data,
+	/// check the length of a [JsMessage::Binary] before copying its data. Messages received as `Blob` are never
+	/// recognized as pong.
+	//
+	pub fn is_pong( mut self, predicate: impl Fn( &JsMessage ) -> bool + 'static ) -> Self
+	{
+		self.is_pong = Rc::new( predicate );
+		self
+	}
+
+
+	/// The close code to use when closing a connection that stopped answering. Browsers only accept some codes,
+	/// see [CloseCode::is_allowed].
+	//
+	pub fn close_code( mut self, code: impl Into<CloseCode> ) -> Self
+	{
+		self.close_code = code.into();
+		self
+	}
+
+
+	pub(crate) fn get_close_code( &self ) -> CloseCode
+	{
+		self.close_code
+	}
+}
+
+
+
+impl fmt::Debug for Heartbeat
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "Heartbeat" )
+
+			.field( "interval"  , &self.interval   )
+			.field( "timeout"   , &self.timeout    )
+			.field( "ping"      , &self.ping       )
+			.field( "close_code", &self.close_code )
+			.finish()
+	}
+}
+
+
+
+// State shared between the message callback of WsStream and the task sending pings.
+//
+#[ derive( Default ) ]
+//
+pub(crate) struct Pongs
+{
+	// When the outstanding ping was sent, in milliseconds since the epoch.
+	//
+	sent_at: Option<f64>,
+
+	// The round trip time of the last ping.
+	//
+	rtt: Option<Duration>,
+
+	// The waker of the task sending pings, waiting for a pong.
+	//
+	waker: Option<Waker>,
+}
+
+
+impl Pongs
+{
+	pub(crate) fn rtt( &self ) -> Option<Duration>
+	{
+		self.rtt
+	}
+}
+
+
+
+/// Returns true if `msg` is a pong, in which case it shouldn't be passed on to the consumer.
+//
+pub(crate) fn receive( heartbeat: &Heartbeat, pongs: &RefCell<Pongs>, notifier: &Notifier, msg: &JsMessage ) -> bool
+{
+	if matches!( msg, JsMessage::Blob(_) ) || !(heartbeat.is_pong)( msg )
+	{
+		return false;
+	}
+
+	let mut pongs = pongs.borrow_mut();
+
+	// An unsolicited pong is still swallowed, but there is nothing to measure.
+	//
+	if let Some( sent_at ) = pongs.sent_at.take()
+	{
+		let rtt = Duration::from_secs_f64( ( js_sys::Date::now() - sent_at ).max( 0.0 ) / 1000.0 );
+
+		pongs.rtt = Some( rtt );
+
+		notifier.notify( WsEvent::Pong{ rtt } );
+
+		if let Some( w ) = pongs.waker.take()
+		{
+			w.wake()
+		}
+	}
+
+	true
+}
+
+
+
+// Send pings and wait for pongs for as long as the connection is open.
+//
+pub(crate) async fn drive
+(
+	heartbeat    : Heartbeat                     ,
+	ws           : SendWrapper< Rc<WebSocket> >  ,
+	pongs        : Rc<RefCell< Pongs >>          ,
+	notifier     : Notifier                      ,
+	local_close  : SendWrapper< Rc<Cell< Option<CloseInitiator> >> > ,
+	close_timeout: Option<Duration>              ,
+)
+{
+	loop
+	{
+		Delay::new( heartbeat.interval ).await;
+
+		match ws.ready_state()
+		{
+			WebSocket::CONNECTING => continue,
+			WebSocket::OPEN       => {}
+			_                     => return,
+		}
+
+		let sent = match &heartbeat.ping
+		{
+			WsMessage::Text  ( text ) => ws.send_with_str     ( text ),
+			WsMessage::Binary( data ) => ws.send_with_u8_array( data ),
+		};
+
+		// The connection closed in the meantime.
+		//
+		if sent.is_err()
+		{
+			return;
+		}
+
+		pongs.borrow_mut().sent_at = Some( js_sys::Date::now() );
+
+		let pong = future::poll_fn( |cx|
+		{
+			let mut pongs = pongs.borrow_mut();
+
+			if pongs.sent_at.is_none()
+			{
+				return Poll::Ready(());
+			}
+
+			pongs.waker = Some( cx.waker().clone() );
+			Poll::Pending
+		});
+
+		if let Either::Left(_) = future::select( pong, Delay::new( heartbeat.timeout ) ).await
+		{
+			continue;
+		}
+
+		pongs.borrow_mut().sent_at = None;
+
+		if ws.ready_state() != WebSocket::OPEN
+		{
+			return;
+		}
+
+		log::warn!( "No pong received within the heartbeat timeout, closing connection to: {}.", ws.url() );
+
+		notifier.notify( WsEvent::HeartbeatTimeout );
+
+		// The close code was validated by WsConfig.
+		//
+		ws.close_with_code( heartbeat.close_code.into() ).expect_throw( "close ws socket" );
+		local_close.set( Some( CloseInitiator::Heartbeat ) );
+
+		notifier.notify( WsEvent::Closing );
+
+		// A dead connection can't complete the closing handshake, so the browser might take a long time to
+		// report it as closed.
+		//
+		if let Some( timeout ) = close_timeout
+		{
+			Delay::new( timeout ).await;
+
+			if ws.ready_state() != WebSocket::CLOSED
+			{
+				force_closed( &ws, &notifier, CloseInitiator::Heartbeat );
+			}
+		}
+
+		return;
+	}
+}
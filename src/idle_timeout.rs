@@ -0,0 +1,51 @@
+use crate::{ import::*, delay::Delay, notifier::Notifier, CloseCode, CloseInitiator, WsEvent };
+
+
+// Close the connection once neither a message was sent nor received for `timeout`. `last_activity` is updated
+// by WsStream, in milliseconds since the epoch.
+//
+pub(crate) async fn drive
+(
+	timeout      : Duration                      ,
+	reason       : String                        ,
+	ws           : SendWrapper< Rc<WebSocket> >  ,
+	last_activity: Rc<Cell<f64>>                 ,
+	notifier     : Notifier                      ,
+	local_close  : SendWrapper< Rc<Cell< Option<CloseInitiator> >> > ,
+)
+{
+	let mut wait = timeout;
+
+	loop
+	{
+		Delay::new( wait ).await;
+
+		match ws.ready_state()
+		{
+			WebSocket::CONNECTING => { wait = timeout; continue; }
+			WebSocket::OPEN       => {}
+			_                     => return,
+		}
+
+		let idle = Duration::from_secs_f64( ( js_sys::Date::now() - last_activity.get() ).max( 0.0 ) / 1000.0 );
+
+		if idle < timeout
+		{
+			wait = timeout - idle;
+			continue;
+		}
+
+		log::info!( "Closing idle connection to: {}.", ws.url() );
+
+		notifier.notify( WsEvent::IdleTimeout );
+
+		// The reason was validated by WsConfig.
+		//
+		ws.close_with_code_and_reason( CloseCode::Normal.into(), &reason ).expect_throw( "close ws socket" );
+		local_close.set( Some( CloseInitiator::Local ) );
+
+		notifier.notify( WsEvent::Closing );
+
+		return;
+	}
+}
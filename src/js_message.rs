@@ -0,0 +1,278 @@
+use crate::{ import::*, ws_reader::Incoming, WsErr, WsMessage, WsReader };
+
+
+/// A WebSocket message whose binary data still lives in JavaScript memory. Obtained through
+/// [WsStream::js_messages](crate::WsStream::js_messages).
+///
+/// This avoids copying binary data into WASM memory when you only need to hand it back to JavaScript,
+/// eg. to WebGL or WebCodecs. The data is only copied when you ask for it, or when converting into
+/// a [WsMessage].
+///
+/// The [JsMessage::Blob] variant only occurs on connections with binary type `Blob`. See
+/// [WsConfig::binary_type](crate::WsConfig::binary_type).
+//
+#[ derive( Debug, Clone ) ]
+//
+pub enum JsMessage
+{
+	/// The data of the message is a string.
+	//
+	Text( String ),
+
+	/// The message contains binary data, which hasn't been copied yet.
+	//
+	Binary( JsBytes ),
+
+	/// The message contains binary data in a `Blob`, which has to be read asynchronously.
+	//
+	Blob( JsBlob ),
+}
+
+
+
+/// Binary data of a [JsMessage], backed by a JavaScript `Uint8Array`.
+//
+#[ derive( Clone ) ]
+//
+pub struct JsBytes
+{
+	buf: SendWrapper< Uint8Array >,
+}
+
+
+
+impl JsBytes
+{
+	pub(crate) fn new( buf: Uint8Array ) -> Self
+	{
+		Self { buf: SendWrapper::new( buf ) }
+	}
+
+
+	/// The length of the data in bytes.
+	//
+	pub fn len( &self ) -> usize
+	{
+		self.buf.length() as usize
+	}
+
+
+	/// Whether the data is empty.
+	//
+	pub fn is_empty( &self ) -> bool
+	{
+		self.len() == 0
+	}
+
+
+	/// Copy the data into a new `Vec`.
+	//
+	pub fn to_vec( &self ) -> Vec<u8>
+	{
+		self.buf.to_vec()
+	}
+
+
+	/// Copy as much of the data as fits into `dst`, starting from the beginning. Returns the number
+	/// of bytes copied.
+	//
+	pub fn copy_into( &self, dst: &mut [u8] ) -> usize
+	{
+		let n = dst.len().min( self.len() );
+
+		self.buf.subarray( 0, n as u32 ).copy_to( &mut dst[..n] );
+
+		n
+	}
+
+
+	/// Access the underlying `Uint8Array` without copying.
+	//
+	pub fn as_uint8_array( &self ) -> &Uint8Array
+	{
+		&self.buf
+	}
+
+
+	/// Access the underlying `ArrayBuffer` without copying.
+	//
+	pub fn array_buffer( &self ) -> ArrayBuffer
+	{
+		self.buf.buffer()
+	}
+}
+
+
+
+/// Binary data of a [JsMessage], backed by a JavaScript `Blob`. The browser might keep the data of a blob out of
+/// the JavaScript heap, which makes this interesting for large payloads.
+//
+#[ derive( Clone ) ]
+//
+pub struct JsBlob
+{
+	blob: SendWrapper< Blob >,
+}
+
+
+
+impl JsBlob
+{
+	/// The size of the data in bytes.
+	//
+	pub fn size( &self ) -> u64
+	{
+		self.blob.size() as u64
+	}
+
+
+	/// Access the underlying `Blob`.
+	//
+	pub fn as_blob( &self ) -> &Blob
+	{
+		&self.blob
+	}
+
+
+	/// Read the data of the blob. Returns [WsErr::CantDecodeBlob] if the browser fails to read it.
+	//
+	pub async fn read( &self ) -> Result< JsBytes, WsErr >
+	{
+		let buf = JsFuture::from( self.blob.array_buffer() ).await
+
+			.map_err( |_| WsErr::CantDecodeBlob )?
+		;
+
+		Ok( JsBytes::new( Uint8Array::new( &buf ) ) )
+	}
+}
+
+
+
+impl fmt::Debug for JsBlob
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "JsBlob {{ size: {} }}", self.size() )
+	}
+}
+
+
+
+impl fmt::Debug for JsBytes
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "JsBytes {{ len: {} }}", self.len() )
+	}
+}
+
+
+
+/// This will convert the JavaScript event into a JsMessage without copying binary data.
+//
+impl TryFrom< MessageEvent > for JsMessage
+{
+	type Error = WsErr;
+
+	fn try_from( evt: MessageEvent ) -> Result< Self, Self::Error >
+	{
+		match evt.data()
+		{
+			d if d.is_instance_of::< ArrayBuffer >() =>
+			{
+				Ok( JsMessage::Binary( JsBytes::new( Uint8Array::new( d.unchecked_ref() ) ) ) )
+			}
+
+
+			// We don't allow invalid encodings. In principle if needed,
+			// we could add a variant to WsMessage with a CString or an OsString
+			// to allow the user to access this data. However until there is a usecase,
+			// I'm not inclined, amongst other things because the conversion from Js isn't very
+			// clear and it would require a bunch of testing for something that's a rather bad
+			// idea to begin with. If you need data that is not a valid string, use a binary
+			// message.
+			//
+			d if d.is_string() =>
+			{
+				match d.as_string()
+				{
+					Some(text) => Ok ( JsMessage::Text( text ) ),
+					None       => Err( WsErr::InvalidEncoding  ),
+				}
+			}
+
+
+			// Only happens when the binary type of the connection is Blob.
+			//
+			d if d.is_instance_of::< Blob >() =>
+			{
+				Ok( JsMessage::Blob( JsBlob{ blob: SendWrapper::new( d.unchecked_into() ) } ) )
+			}
+
+
+			// should never happen.
+			//
+			_ => Err( WsErr::UnknownDataType ),
+		}
+	}
+}
+
+
+/// Copies binary data into WASM memory. A `Blob` can only be read asynchronously, so it results in
+/// [WsErr::CantDecodeBlob]. Use [JsBlob::read] for those.
+//
+impl TryFrom<JsMessage> for WsMessage
+{
+	type Error = WsErr;
+
+	fn try_from( msg: JsMessage ) -> Result< Self, Self::Error >
+	{
+		match msg
+		{
+			JsMessage::Text  ( text  ) => Ok ( WsMessage::Text  ( text           ) ),
+			JsMessage::Binary( bytes ) => Ok ( WsMessage::Binary( bytes.to_vec() ) ),
+			JsMessage::Blob  ( _     ) => Err( WsErr::CantDecodeBlob               ),
+		}
+	}
+}
+
+
+
+/// A `Stream` of [JsMessage] borrowing a [WsStream](crate::WsStream) or a [WsReader]. Created with
+/// [WsStream::js_messages](crate::WsStream::js_messages) or [WsReader::js_messages].
+//
+#[ derive( Debug ) ]
+//
+pub struct JsMessages<'a>
+{
+	inner: &'a mut WsReader,
+}
+
+
+impl<'a> JsMessages<'a>
+{
+	pub(crate) fn new( inner: &'a mut WsReader ) -> Self
+	{
+		Self { inner }
+	}
+}
+
+
+impl Stream for JsMessages<'_>
+{
+	type Item = JsMessage;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		loop
+		{
+			match ready!( self.inner.poll_next_incoming( cx, false, false ) )
+			{
+				Some( Incoming::Message( msg ) ) => return Some( msg ).into(),
+				Some( Incoming::Event  ( _   ) ) => continue,
+				None                             => return None.into(),
+			}
+		}
+	}
+}
@@ -4,7 +4,7 @@
 
 #![ doc    ( html_root_url = "https://docs.rs/ws_stream_wasm"            ) ]
 #![ forbid ( unsafe_code                                                 ) ]
-#![ allow  ( clippy::suspicious_else_formatting, clippy::needless_return ) ]
+#![ allow  ( clippy::suspicious_else_formatting, clippy::needless_return, clippy::empty_docs ) ]
 
 
 #![ warn
@@ -24,23 +24,55 @@
 
 
 
-mod error        ;
-mod ws_event     ;
-mod ws_message   ;
-mod ws_meta      ;
-mod ws_state     ;
-mod ws_stream    ;
-mod ws_stream_io ;
+mod close_code          ;
+mod connect_params      ;
+mod delay               ;
+mod error               ;
+mod failover            ;
+mod heartbeat           ;
+mod idle_timeout        ;
+mod js_message          ;
+mod notifier            ;
+mod outbox              ;
+mod reconnect_config    ;
+mod reconnecting_meta   ;
+mod reconnecting_stream ;
+mod sub_protocol        ;
+mod ws_config           ;
+mod ws_event            ;
+mod ws_item             ;
+mod ws_message          ;
+mod ws_meta             ;
+mod ws_reader           ;
+mod ws_sender           ;
+mod ws_state            ;
+mod ws_stream           ;
+mod ws_stream_io        ;
+mod ws_writer           ;
 
 pub use
 {
-	error        :: { WsErr               } ,
-	ws_event     :: { WsEvent, CloseEvent } ,
-	ws_message   :: { WsMessage           } ,
-	ws_meta      :: { WsMeta              } ,
-	ws_state     :: { WsState             } ,
-	ws_stream    :: { WsStream            } ,
-	ws_stream_io :: { WsStreamIo          } ,
+	close_code          :: { CloseCode                                   } ,
+	connect_params      :: { ConnectParams, ConnectParamsError, BoxError } ,
+	error               :: { WsErr, UrlError                             } ,
+	failover            :: { Failover, FailoverOrder                     } ,
+	heartbeat           :: { Heartbeat                                   } ,
+	js_message          :: { JsMessage, JsBytes, JsBlob, JsMessages      } ,
+	reconnect_config    :: { ReconnectConfig                             } ,
+	reconnecting_meta   :: { ReconnectingWsMeta                          } ,
+	reconnecting_stream :: { ReconnectingWsStream                        } ,
+	sub_protocol        :: { SubProtocol                                 } ,
+	ws_config           :: { WsConfig, OverflowPolicy, OutboxPolicy      } ,
+	ws_event            :: { WsEvent, CloseEvent, CloseInitiator         } ,
+	ws_item             :: { WsItem, WsItems                             } ,
+	ws_message          :: { WsMessage, TryMessages                      } ,
+	ws_meta             :: { WsMeta                                      } ,
+	ws_reader           :: { WsReader                                    } ,
+	ws_sender           :: { WsSender                                    } ,
+	ws_state            :: { WsState, WsStateWatch                       } ,
+	ws_stream           :: { WsStream, ReuniteError                      } ,
+	ws_stream_io        :: { WsStreamIo                                  } ,
+	ws_writer           :: { WsWriter                                    } ,
 };
 
 
@@ -49,34 +81,19 @@ mod import
 {
 	pub(crate) use
 	{
-		futures              :: { prelude::{ Stream, Sink }, ready, StreamExt, FutureExt                         } ,
-		std                  :: { io, collections::VecDeque, fmt, task::{ Context, Waker, Poll }, future::Future } ,
-		std                  :: { rc::Rc, cell::{ RefCell }, pin::Pin, convert::{ TryFrom, TryInto }             } ,
-		js_sys               :: { ArrayBuffer, Uint8Array                                                        } ,
-		wasm_bindgen         :: { closure::Closure, JsCast, JsValue, UnwrapThrowExt                              } ,
-		web_sys              :: { *, BinaryType, Blob, WebSocket, CloseEvent as JsCloseEvt, DomException         } ,
-		js_sys               :: { Array                                                                          } ,
-		pharos               :: { SharedPharos, PharErr, Observable, Observe, Filter, ObserveConfig,             } ,
-		wasm_bindgen_futures :: { spawn_local                                                                    } ,
-		async_io_stream      :: { IoStream                                                                       } ,
-		thiserror            :: { Error                                                                          } ,
-		send_wrapper         :: { SendWrapper                                                                    } ,
+		futures              :: { prelude::{ Stream, Sink }, ready, StreamExt, FutureExt, task::noop_waker_ref       } ,
+		futures              :: { future::{ self, AbortHandle, Either }                                              } ,
+		std                  :: { io, collections::VecDeque, fmt, task::{ Context, Waker, Poll }, future::Future     } ,
+		std                  :: { rc::{ Rc, Weak }, cell::{ Cell, RefCell }, pin::Pin, convert::{ TryFrom, TryInto } } ,
+		std                  :: { time::Duration, sync::Arc, hash::{ Hash, Hasher }                                  } ,
+		js_sys               :: { ArrayBuffer, Uint8Array, Function, Reflect                                         } ,
+		wasm_bindgen         :: { closure::Closure, JsCast, JsValue, UnwrapThrowExt, prelude::wasm_bindgen           } ,
+		web_sys              :: { *, BinaryType, Blob, WebSocket, CloseEvent as JsCloseEvt, DomException             } ,
+		js_sys               :: { Array                                                                              } ,
+		pharos               :: { Pharos, PharErr, Observable, Observe, Filter, ObserveConfig, Events, Channel       } ,
+		wasm_bindgen_futures :: { spawn_local, JsFuture                                                              } ,
+		async_io_stream      :: { IoStream                                                                           } ,
+		thiserror            :: { Error                                                                              } ,
+		send_wrapper         :: { SendWrapper                                                                        } ,
 	};
 }
-
-
-use import::*;
-
-/// Helper function to reduce code bloat
-//
-pub(crate) fn notify( pharos: SharedPharos<WsEvent>, evt: WsEvent )
-{
-	let notify = async move
-	{
-		pharos.notify( evt ).await
-
-			.map_err( |e| unreachable!( "{:?}", e ) ).unwrap(); // only happens if we closed it.
-	};
-
-	spawn_local( notify );
-}
@@ -0,0 +1,244 @@
+use crate::{ import::*, ws_reader::Inbox, CloseEvent, WsEvent };
+
+
+// Delivers the events of a connection to the observers of WsMeta and to the receive queue of WsStream. Events
+// are put in the queue right away, so they keep their order relative to the incoming messages.
+//
+// Observers receive events synchronously from the browser callback that caused them. Only when the channel of
+// an observer is full do we spawn a task to deliver the remaining events once it has room. Until that task is
+// done, new events are queued behind the ones it's delivering, so observers always see events in order.
+//
+#[ derive( Clone ) ]
+//
+pub(crate) struct Notifier
+{
+	dispatch: SendWrapper< Rc<RefCell< Dispatch    >> > ,
+	tap     : SendWrapper< Rc<RefCell< Option<Tap> >> > ,
+
+	// The channel of the observers we create ourselves, see WsConfig::observer_channel.
+	//
+	channel: Channel,
+}
+
+
+
+// The receive queue of WsStream and the waker of the task reading it.
+//
+struct Tap
+{
+	queue: Weak<RefCell< Inbox >>       ,
+	waker: Rc<RefCell< Option<Waker> >> ,
+}
+
+
+
+// The observers and the events that haven't been delivered to them yet.
+//
+struct Dispatch
+{
+	pharos : Pharos<WsEvent>   ,
+	pending: VecDeque<WsEvent> ,
+
+	// The close event, once the connection has closed.
+	//
+	closed: Option<CloseEvent> ,
+
+	// Whether a task is delivering the pending events.
+	//
+	spawned: bool ,
+
+	// Whether an Open event still has to be sent for a socket that was already open when we took it over.
+	//
+	open_pending: bool ,
+}
+
+
+
+impl Dispatch
+{
+	// Deliver pending events in order until an observer has no room.
+	//
+	fn poll_deliver( &mut self, cx: &mut Context<'_> ) -> Poll<()>
+	{
+		while !self.pending.is_empty()
+		{
+			match Pin::new( &mut self.pharos ).poll_ready( cx )
+			{
+				Poll::Pending         => return Poll::Pending,
+				Poll::Ready( Ok(()) ) => {}
+
+				// Only happens if we closed pharos, which we never do.
+				//
+				Poll::Ready( Err(e) ) => unreachable!( "{:?}", e ),
+			}
+
+			let evt = self.pending.pop_front().expect_throw( "pending is not empty" );
+
+			Pin::new( &mut self.pharos ).start_send( evt ).expect_throw( "pharos is not closed" );
+		}
+
+		// The events are in the channels once start_send returns, flushing only checks whether there is room
+		// for more, which the next poll_ready does anyway.
+		//
+		Poll::Ready(())
+	}
+}
+
+
+
+impl Notifier
+{
+	pub(crate) fn new( observer_capacity: usize, channel: Channel ) -> Self
+	{
+		let dispatch = Dispatch
+		{
+			pharos      : Pharos::new( observer_capacity ) ,
+			pending     : VecDeque::new()                 ,
+			closed      : None                            ,
+			spawned     : false                           ,
+			open_pending: false                           ,
+		};
+
+		Self
+		{
+			dispatch: SendWrapper::new( Rc::new( RefCell::new( dispatch ) ) ),
+			tap     : SendWrapper::new( Rc::new( RefCell::new( None     ) ) ),
+			channel                                                          ,
+		}
+	}
+
+
+	// Add an observer. Pharos doesn't need to wait for anything to do so, so this doesn't need to be async.
+	//
+	pub(crate) fn observe( &self, options: ObserveConfig<WsEvent> ) -> Result< Events<WsEvent>, PharErr >
+	{
+		self.dispatch.borrow_mut().pharos.observe( options ).now_or_never()
+
+			.expect_throw( "Pharos::observe is ready immediately" )
+	}
+
+
+	// The options for an observer we create ourselves.
+	//
+	pub(crate) fn options( &self, filter: Filter<WsEvent> ) -> ObserveConfig<WsEvent>
+	{
+		ObserveConfig::from( filter ).channel( self.channel )
+	}
+
+
+	// The close event of the connection, if it has closed.
+	//
+	pub(crate) fn closed( &self ) -> Option<CloseEvent>
+	{
+		self.dispatch.borrow().closed.clone()
+	}
+
+
+	// Start putting events in the receive queue of a WsStream.
+	//
+	pub(crate) fn attach( &self, queue: &Rc<RefCell< Inbox >>, waker: &Rc<RefCell< Option<Waker> >> )
+	{
+		*self.tap.borrow_mut() = Some( Tap{ queue: Rc::downgrade( queue ), waker: waker.clone() } );
+	}
+
+
+	// Send an Open event for a socket that was already open when we took it over. Observers are notified
+	// synchronously, so doing it right away would reach nobody. Instead it's sent from a task, which runs once the
+	// caller had a chance to observe and the receive queue is attached, but before the browser delivers anything
+	// else. Should another event come first anyway, the Open event is sent before it.
+	//
+	pub(crate) fn replay_open( &self )
+	{
+		self.dispatch.borrow_mut().open_pending = true;
+
+		let notifier = self.clone();
+
+		spawn_local( async move { notifier.flush_open() } );
+	}
+
+
+	// Send the Open event of replay_open if that hasn't happened yet.
+	//
+	fn flush_open( &self )
+	{
+		let pending = std::mem::take( &mut self.dispatch.borrow_mut().open_pending );
+
+		if pending
+		{
+			self.notify( WsEvent::Open );
+		}
+	}
+
+
+	// Put the event in the receive queue and notify observers.
+	//
+	pub(crate) fn notify( &self, evt: WsEvent )
+	{
+		self.flush_open();
+
+		if let Some( tap ) = &*self.tap.borrow()
+		{
+			// When the reader has been dropped, nobody will consume the event.
+			//
+			if let Some( queue ) = tap.queue.upgrade()
+			{
+				queue.borrow_mut().push_event( evt.clone() );
+
+				if let Some( w ) = tap.waker.borrow_mut().take()
+				{
+					w.wake()
+				}
+			}
+		}
+
+		self.notify_observers( evt );
+	}
+
+
+	// Only notify observers, for events that are already in the receive queue.
+	//
+	pub(crate) fn notify_observers( &self, evt: WsEvent )
+	{
+		self.flush_open();
+
+		let mut dispatch = self.dispatch.borrow_mut();
+
+		if let WsEvent::Closed( ce ) = &evt
+		{
+			dispatch.closed = Some( ce.clone() );
+		}
+
+		dispatch.pending.push_back( evt );
+
+		// The task will deliver this event after the ones before it.
+		//
+		if dispatch.spawned
+		{
+			return;
+		}
+
+		// With the noop waker, an observer without room just makes us fall back to the task, which registers
+		// a real waker.
+		//
+		if dispatch.poll_deliver( &mut Context::from_waker( noop_waker_ref() ) ).is_ready()
+		{
+			return;
+		}
+
+		dispatch.spawned = true;
+		drop( dispatch );
+
+		let shared = self.dispatch.clone().take();
+
+		spawn_local( future::poll_fn( move |cx|
+		{
+			let mut d = shared.borrow_mut();
+
+			ready!( d.poll_deliver( cx ) );
+
+			d.spawned = false;
+
+			Poll::Ready(())
+		}));
+	}
+}
@@ -0,0 +1,98 @@
+use crate::{ import::*, notifier::Notifier, OutboxPolicy, WsErr, WsEvent, WsMessage };
+
+
+// Messages sent while the connection is connecting, see WsConfig::outbox. Shared between the halves of
+// WsStream, the senders and the task that flushes it when the connection opens. ReconnectingWsStream has
+// its own, for the messages sent while it reconnects.
+//
+pub(crate) struct Outbox
+{
+	queue   : RefCell< VecDeque<WsMessage> > ,
+	capacity: usize                          ,
+	policy  : OutboxPolicy                   ,
+}
+
+
+
+impl Outbox
+{
+	pub(crate) fn new( capacity: usize, policy: OutboxPolicy ) -> Self
+	{
+		Self
+		{
+			queue: RefCell::new( VecDeque::with_capacity( capacity ) ),
+			capacity                                                 ,
+			policy                                                   ,
+		}
+	}
+
+
+	pub(crate) fn has_room( &self ) -> bool
+	{
+		self.queue.borrow().len() < self.capacity
+	}
+
+
+	pub(crate) fn push( &self, msg: WsMessage ) -> Result<(), WsErr>
+	{
+		if !self.has_room()
+		{
+			return Err( WsErr::OutboxFull );
+		}
+
+		self.queue.borrow_mut().push_back( msg );
+
+		Ok(())
+	}
+
+
+	// Hand the queued messages to the browser and record the activity for the idle timeout. Only call this
+	// when the connection is open.
+	//
+	pub(crate) fn flush( &self, ws: &WebSocket, last_activity: &Cell<f64> )
+	{
+		let mut queue = self.queue.borrow_mut();
+
+		if queue.is_empty()
+		{
+			return;
+		}
+
+		last_activity.set( js_sys::Date::now() );
+
+		for msg in queue.drain(..)
+		{
+			// Can only fail if the connection is no longer open, in which case the remaining messages are lost
+			// just like messages already in the send buffer of the browser.
+			//
+			let _ = match msg
+			{
+				WsMessage::Binary( d ) => ws.send_with_u8_array( &d ),
+				WsMessage::Text  ( s ) => ws.send_with_str     ( &s ),
+			};
+		}
+	}
+
+
+	// No connection opened to send the queued messages on, apply the policy to them.
+	//
+	pub(crate) fn fail( &self, notifier: &Notifier )
+	{
+		let msgs: Vec<WsMessage> = self.queue.borrow_mut().drain(..).collect();
+
+		if msgs.is_empty()
+		{
+			return;
+		}
+
+		match self.policy
+		{
+			OutboxPolicy::Discard =>
+			{
+				log::warn!( "No connection opened to send them on, discarding {} queued messages.", msgs.len() );
+			}
+
+			OutboxPolicy::Report => notifier.notify( WsEvent::Undelivered( msgs ) ),
+		}
+	}
+}
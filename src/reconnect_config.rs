@@ -0,0 +1,164 @@
+use crate::{ import::*, CloseEvent };
+
+
+/// Configuration for [ReconnectingWsMeta::connect](crate::ReconnectingWsMeta::connect). Controls how long
+/// to wait between attempts to re-establish a lost connection, how often to try and which close events
+/// warrant a reconnect at all.
+///
+/// The delay before attempt `n` (starting at 1) is `initial_delay * multiplier^(n-1)`, capped at `max_delay`.
+/// A random fraction of up to `jitter` of that delay is then subtracted, so that many clients that lost their
+/// connection at the same time don't all hammer the server at the same moment.
+///
+/// The defaults are: an initial delay of 1 second, a maximum delay of 30 seconds, a multiplier of 2, a jitter of
+/// 0.5, an unlimited number of attempts and to retry on every close event except a clean close with
+/// [CloseCode::Normal](crate::CloseCode::Normal).
+///
+/// ```
+/// use { ws_stream_wasm::*, std::time::Duration };
+///
+/// let config = ReconnectConfig::default()
+///
+///    .initial_delay( Duration::from_millis( 500 ) )
+///    .max_attempts ( 10                           )
+///    .retry_if     ( |evt| evt.is_abnormal()      )
+/// ;
+/// ```
+//
+#[ derive( Clone ) ]
+//
+pub struct ReconnectConfig
+{
+	initial_delay: Duration                            ,
+	max_delay    : Duration                            ,
+	multiplier   : f64                                 ,
+	jitter       : f64                                 ,
+	max_attempts : Option<u32>                         ,
+	retry_if     : Rc< dyn Fn( &CloseEvent ) -> bool > ,
+}
+
+
+
+impl Default for ReconnectConfig
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			initial_delay: Duration::from_secs( 1  ) ,
+			max_delay    : Duration::from_secs( 30 ) ,
+			multiplier   : 2.0                       ,
+			jitter       : 0.5                       ,
+			max_attempts : None                      ,
+
+			// Don't reconnect when the server closed normally.
+			//
+			retry_if: Rc::new( |evt: &CloseEvent| !evt.is_normal() ),
+		}
+	}
+}
+
+
+
+impl ReconnectConfig
+{
+	/// The delay before the first reconnection attempt.
+	//
+	pub fn initial_delay( mut self, delay: Duration ) -> Self
+	{
+		self.initial_delay = delay;
+		self
+	}
+
+
+	/// The upper bound for the delay between attempts.
+	//
+	pub fn max_delay( mut self, delay: Duration ) -> Self
+	{
+		self.max_delay = delay;
+		self
+	}
+
+
+	/// The factor by which the delay grows after each failed attempt. Values below 1 are treated as 1.
+	//
+	pub fn multiplier( mut self, multiplier: f64 ) -> Self
+	{
+		self.multiplier = multiplier.max( 1.0 );
+		self
+	}
+
+
+	/// The fraction of the delay that can randomly be subtracted from it. Clamped to `0.0..=1.0`.
+	//
+	pub fn jitter( mut self, jitter: f64 ) -> Self
+	{
+		self.jitter = jitter.clamp( 0.0, 1.0 );
+		self
+	}
+
+
+	/// Give up after this many consecutive failed attempts. The counter is reset after every successful
+	/// reconnection.
+	//
+	pub fn max_attempts( mut self, attempts: u32 ) -> Self
+	{
+		self.max_attempts = Some( attempts );
+		self
+	}
+
+
+	/// A predicate deciding whether a connection that was closed with the given [CloseEvent] should be
+	/// re-established. Connections closed through [ReconnectingWsMeta](crate::ReconnectingWsMeta) or
+	/// [ReconnectingWsStream](crate::ReconnectingWsStream) are never re-established.
+	//
+	pub fn retry_if( mut self, predicate: impl Fn( &CloseEvent ) -> bool + 'static ) -> Self
+	{
+		self.retry_if = Rc::new( predicate );
+		self
+	}
+
+
+	/// Whether a connection that was closed with this event should be re-established.
+	//
+	pub(crate) fn should_retry( &self, evt: &CloseEvent ) -> bool
+	{
+		(self.retry_if)( evt )
+	}
+
+
+	/// Whether we are still allowed to make attempt number `attempt` (starting at 1).
+	//
+	pub(crate) fn allows_attempt( &self, attempt: u32 ) -> bool
+	{
+		self.max_attempts.map( |max| attempt <= max ).unwrap_or( true )
+	}
+
+
+	/// The delay to wait before attempt number `attempt` (starting at 1), jitter included.
+	//
+	pub(crate) fn delay( &self, attempt: u32 ) -> Duration
+	{
+		let exp   = self.multiplier.powi( attempt.saturating_sub( 1 ).min( i32::MAX as u32 ) as i32 );
+		let base  = ( self.initial_delay.as_secs_f64() * exp ).min( self.max_delay.as_secs_f64() );
+		let delay = base * ( 1.0 - self.jitter * js_sys::Math::random() );
+
+		Duration::from_secs_f64( delay.max( 0.0 ) )
+	}
+}
+
+
+
+impl fmt::Debug for ReconnectConfig
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		f.debug_struct( "ReconnectConfig" )
+
+			.field( "initial_delay", &self.initial_delay )
+			.field( "max_delay"    , &self.max_delay     )
+			.field( "multiplier"   , &self.multiplier    )
+			.field( "jitter"       , &self.jitter        )
+			.field( "max_attempts" , &self.max_attempts  )
+			.finish()
+	}
+}
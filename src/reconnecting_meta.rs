@@ -0,0 +1,413 @@
+use crate::{ import::*, delay::Delay, notifier::Notifier, outbox::Outbox, WsConfig, WsErr, WsState, WsStream, WsMeta, WsEvent, CloseEvent, CloseCode, ReconnectConfig, ReconnectingWsStream };
+
+
+// State shared between ReconnectingWsMeta, ReconnectingWsStream and the task that re-establishes
+// the connection.
+//
+pub(crate) struct Shared
+{
+	// The meta data of the current connection.
+	//
+	pub(crate) meta: Rc<WsMeta>,
+
+	// A freshly established connection, waiting to be picked up by ReconnectingWsStream.
+	//
+	pub(crate) next: Option< WsStream >,
+
+	// Set when the user closed the connection, in which case we don't reconnect.
+	//
+	pub(crate) user_closed: bool,
+
+	// Set when we stopped trying to reconnect.
+	//
+	pub(crate) gave_up: bool,
+
+	// Last waker of task that wants to read incoming messages.
+	//
+	pub(crate) waker: Option<Waker>,
+
+	// Last waker of task that wants to write to the Sink.
+	//
+	pub(crate) sink_waker: Option<Waker>,
+
+	// Messages sent while reconnecting, if WsConfig::outbox is set. Flushed into the next connection.
+	//
+	pub(crate) outbox: Option<Outbox>,
+}
+
+
+impl Shared
+{
+	pub(crate) fn new( meta: WsMeta, outbox: Option<Outbox> ) -> Self
+	{
+		Self
+		{
+			meta       : Rc::new( meta ),
+			next       : None           ,
+			user_closed: false          ,
+			gave_up    : false          ,
+			waker      : None           ,
+			sink_waker : None           ,
+			outbox                      ,
+		}
+	}
+
+
+	pub(crate) fn wake( &mut self )
+	{
+		if let Some(w) = self.waker     .take() { w.wake() }
+		if let Some(w) = self.sink_waker.take() { w.wake() }
+	}
+}
+
+
+
+/// The equivalent of [WsMeta] for a connection that is automatically re-established when lost.
+/// Created with [ReconnectingWsMeta::connect], which also returns a [ReconnectingWsStream].
+///
+/// Observers receive the events of every underlying connection, including the [WsEvent::Closed] of a lost
+/// connection. When a reconnection is scheduled, [WsEvent::Reconnecting] is emitted, followed by
+/// [WsEvent::Reconnected] once a new connection has been established.
+///
+/// Closing the connection through this object or through [ReconnectingWsStream] stops reconnection. Methods that
+/// query the connection, like [url](ReconnectingWsMeta::url) or [protocol](ReconnectingWsMeta::protocol), refer
+/// to the current underlying connection.
+//
+pub struct ReconnectingWsMeta
+{
+	shared  : SendWrapper< Rc<RefCell< Shared >> >,
+	notifier: Notifier                            ,
+}
+
+
+
+impl ReconnectingWsMeta
+{
+	/// Connect to the server. The future will resolve when the first connection has been established. Failing to
+	/// establish this first connection is reported as an error, exactly like [WsMeta::connect] does, without any
+	/// retries.
+	///
+	/// Afterwards, whenever the connection is lost and [ReconnectConfig::retry_if] accepts the [CloseEvent], a new
+	/// connection to the same url with the same protocols will be established in the background, with delays
+	/// determined by `config`.
+	///
+	/// To set other options on the connection, use [ReconnectingWsMeta::connect_with].
+	//
+	pub async fn connect
+	(
+		url      : impl AsRef<str>              ,
+		protocols: impl Into<Option<Vec<&str>>> ,
+		config   : ReconnectConfig              ,
+	)
+		-> Result< (Self, ReconnectingWsStream), WsErr >
+	{
+		let mut ws_config = WsMeta::builder( url );
+
+		if let Some( protocols ) = protocols.into()
+		{
+			ws_config = ws_config.protocols( protocols );
+		}
+
+		Self::connect_with( ws_config, config ).await
+	}
+
+
+
+	/// Like [ReconnectingWsMeta::connect], but every connection is established with the options in `ws_config`.
+	//
+	pub async fn connect_with( ws_config: WsConfig, config: ReconnectConfig )
+
+		-> Result< (Self, ReconnectingWsStream), WsErr >
+	{
+		let (mut meta, stream) = ws_config.clone().connect().await?;
+
+		let evts = meta.observe( ws_config.observer_channel.into() ).await.expect_throw( "observe WsMeta" );
+
+		let notifier = Notifier::new( ws_config.observer_capacity, ws_config.observer_channel );
+		let outbox   = ws_config.outbox.map( |(capacity, policy)| Outbox::new( capacity, policy ) );
+		let shared   = SendWrapper::new( Rc::new( RefCell::new( Shared::new( meta, outbox ) ) ) );
+
+		let driver = drive( evts, ws_config, config, shared.clone(), notifier.clone() );
+		let (driver, abort) = future::abortable( driver );
+
+		spawn_local( driver.map( |_| () ) );
+
+		Ok
+		((
+			Self
+			{
+				shared  : shared  .clone(),
+				notifier: notifier.clone(),
+			},
+
+			ReconnectingWsStream::new( stream, shared, notifier, abort ),
+		))
+	}
+
+
+
+	/// Close the socket and stop reconnecting. The future will resolve once the current connection is closed.
+	/// If the connection was lost and we are waiting to reconnect, [WsErr::ConnectionNotOpen] is returned.
+	/// See [WsMeta::close].
+	//
+	pub async fn close( &self ) -> Result< CloseEvent, WsErr >
+	{
+		self.stop().close().await
+	}
+
+
+	/// Close the socket with a code and stop reconnecting. See [WsMeta::close_code].
+	//
+	pub async fn close_code( &self, code: impl Into<CloseCode> ) -> Result< CloseEvent, WsErr >
+	{
+		self.stop().close_code( code ).await
+	}
+
+
+	/// Close the socket with a code and a reason and stop reconnecting. See [WsMeta::close_reason].
+	//
+	pub async fn close_reason( &self, code: impl Into<CloseCode>, reason: impl AsRef<str> ) -> Result< CloseEvent, WsErr >
+	{
+		self.stop().close_reason( code, reason ).await
+	}
+
+
+	// Mark the connection as closed by the user and get the current meta.
+	//
+	fn stop( &self ) -> Rc<WsMeta>
+	{
+		let mut shared = self.shared.borrow_mut();
+
+		shared.user_closed = true;
+
+		shared.meta.clone()
+	}
+
+
+	/// Verify the [WsState] of the connection. While waiting to reconnect, this reports [WsState::Connecting].
+	//
+	pub fn ready_state( &self ) -> WsState
+	{
+		let shared = self.shared.borrow();
+
+		let state = shared.meta.ready_state();
+
+		if state == WsState::Closed && !shared.gave_up && !shared.user_closed
+		{
+			return WsState::Connecting;
+		}
+
+		state
+	}
+
+
+	/// The number of bytes of data that have been queued but not yet transmitted to the network on the
+	/// current connection. See [WsMeta::buffered_amount].
+	//
+	pub fn buffered_amount( &self ) -> u32
+	{
+		self.with_meta( WsMeta::buffered_amount )
+	}
+
+
+	/// The extensions selected by the server for the current connection. See [WsMeta::extensions].
+	//
+	pub fn extensions( &self ) -> String
+	{
+		self.with_meta( WsMeta::extensions )
+	}
+
+
+	/// The name of the sub-protocol the server selected for the current connection. See [WsMeta::protocol].
+	//
+	pub fn protocol( &self ) -> String
+	{
+		self.with_meta( WsMeta::protocol )
+	}
+
+
+	/// Retrieve the address to which this socket is connected.
+	//
+	pub fn url( &self ) -> String
+	{
+		self.with_meta( WsMeta::url )
+	}
+
+
+	/// Access the [WsMeta] of the current underlying connection.
+	///
+	/// ## Caveats
+	/// Closing the connection through this object will look to _ws_stream_wasm_ like a lost connection,
+	/// and it will reconnect if [ReconnectConfig::retry_if] accepts the close event.
+	//
+	pub fn current( &self ) -> Rc<WsMeta>
+	{
+		self.shared.borrow().meta.clone()
+	}
+
+
+	fn with_meta<T>( &self, f: impl FnOnce( &WsMeta ) -> T ) -> T
+	{
+		f( &self.current() )
+	}
+}
+
+
+
+// Forward events from the current connection and re-establish it when it's lost.
+//
+async fn drive
+(
+	mut evts : Events<WsEvent>                      ,
+	ws_config: WsConfig                             ,
+	config   : ReconnectConfig                      ,
+	shared   : SendWrapper< Rc<RefCell< Shared >> > ,
+	notifier : Notifier                             ,
+)
+{
+	loop
+	{
+		// Forward events until the connection closes.
+		//
+		let close_evt = loop
+		{
+			match evts.next().await
+			{
+				Some( WsEvent::Closed(evt) ) =>
+				{
+					notifier.notify( WsEvent::Closed( evt.clone() ) );
+					break evt;
+				}
+
+				Some( evt ) => notifier.notify( evt ),
+
+				// The connection was dropped without emitting a close event.
+				//
+				None => return give_up( &shared, &notifier ),
+			}
+		};
+
+
+		if shared.borrow().user_closed || !config.should_retry( &close_evt )
+		{
+			return give_up( &shared, &notifier );
+		}
+
+
+		let mut attempt = 0;
+		let mut refresh = ws_config.rejects_credentials( &close_evt );
+
+		let (mut meta, stream) = loop
+		{
+			attempt += 1;
+
+			if !config.allows_attempt( attempt )
+			{
+				log::warn!( "Giving up reconnecting to {} after {} attempts.", ws_config.url, attempt - 1 );
+				return give_up( &shared, &notifier );
+			}
+
+			notifier.notify( WsEvent::Reconnecting{ attempt } );
+
+			Delay::new( config.delay( attempt ) ).await;
+
+			if shared.borrow().user_closed
+			{
+				return give_up( &shared, &notifier );
+			}
+
+			let mut attempt_config = ws_config.clone();
+
+			attempt_config.refresh_params = refresh;
+
+			match attempt_config.connect().await
+			{
+				Ok ( pair                            ) => break pair,
+				Err( WsErr::ConnectTimeout           ) => continue  ,
+				Err( WsErr::ConnectParams{ .. }      ) => continue  ,
+
+				Err( WsErr::ConnectionFailed{ event } ) =>
+				{
+					refresh = ws_config.rejects_credentials( &event );
+					continue;
+				}
+
+				// Anything else will not get better by trying again.
+				//
+				Err( e ) =>
+				{
+					log::warn!( "Giving up reconnecting to {}: {}.", ws_config.url, e );
+					return give_up( &shared, &notifier );
+				}
+			}
+		};
+
+
+		evts = meta.observe( ws_config.observer_channel.into() ).await.expect_throw( "observe WsMeta" );
+
+		{
+			let mut s = shared.borrow_mut();
+
+			// The user closed while we were connecting. Dropping stream closes the new connection.
+			//
+			if s.user_closed
+			{
+				drop( s );
+				drop( stream );
+				return give_up( &shared, &notifier );
+			}
+
+			// Send what was queued while reconnecting before anything else can be sent on the new connection.
+			//
+			if let Some( outbox ) = &s.outbox
+			{
+				stream.core().flush_outbox( outbox );
+			}
+
+			s.meta = Rc::new( meta );
+			s.next = Some( stream );
+			s.wake();
+		}
+
+		notifier.notify( WsEvent::Reconnected );
+	}
+}
+
+
+
+// Messages still queued in the outbox will not be sent, apply the policy.
+//
+fn give_up( shared: &Rc<RefCell< Shared >>, notifier: &Notifier )
+{
+	let mut s = shared.borrow_mut();
+
+	s.gave_up = true;
+	s.wake();
+
+	if let Some( outbox ) = &s.outbox
+	{
+		outbox.fail( notifier );
+	}
+}
+
+
+
+impl fmt::Debug for ReconnectingWsMeta
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "ReconnectingWsMeta for connection: {}", self.url() )
+	}
+}
+
+
+
+impl Observable<WsEvent> for ReconnectingWsMeta
+{
+	type Error = PharErr;
+
+	fn observe( &mut self, options: ObserveConfig<WsEvent> ) -> Observe< '_, WsEvent, Self::Error >
+	{
+		future::ready( self.notifier.observe( options ) ).boxed()
+	}
+}
@@ -0,0 +1,254 @@
+use crate::{ import::*, notifier::Notifier, outbox::Outbox, reconnecting_meta::Shared, WsErr, WsState, WsStream, WsEvent, WsMessage };
+
+
+/// The equivalent of [WsStream] for a connection that is automatically re-established when lost. Created with
+/// [ReconnectingWsMeta::connect](crate::ReconnectingWsMeta::connect).
+///
+/// The `Stream` yields the messages of every underlying connection in order. When a connection is lost, the
+/// messages still buffered from it are yielded before those of the new connection. The stream only ends once
+/// reconnection stopped, either because the connection was closed on purpose, because the close event wasn't
+/// accepted by [ReconnectConfig::retry_if](crate::ReconnectConfig::retry_if) or because the maximum number of
+/// attempts was reached.
+///
+/// While reconnecting, `Sink::poll_ready` will return `Pending` until a new connection is established. With
+/// [WsConfig::outbox](crate::WsConfig::outbox), messages are accepted while reconnecting instead, as long as the
+/// outbox has room, and sent in order on the new connection before anything sent after it was established. When
+/// reconnection stops, the queued messages are handled according to the [OutboxPolicy](crate::OutboxPolicy).
+/// Messages that are sent on a connection right before it is lost might never reach the server.
+///
+/// Dropping or closing this stops reconnection and closes the current connection.
+//
+pub struct ReconnectingWsStream
+{
+	current : WsStream                            ,
+	retired : VecDeque<WsStream>                  ,
+	shared  : SendWrapper< Rc<RefCell< Shared >> > ,
+	notifier: Notifier                            ,
+	abort   : AbortHandle                         ,
+}
+
+
+
+impl ReconnectingWsStream
+{
+	pub(crate) fn new
+	(
+		current : WsStream                            ,
+		shared  : SendWrapper< Rc<RefCell< Shared >> > ,
+		notifier: Notifier                            ,
+		abort   : AbortHandle                         ,
+	)
+		-> Self
+	{
+		Self { current, retired: VecDeque::new(), shared, notifier, abort }
+	}
+
+
+	/// Verify the [WsState] of the current connection. While waiting to reconnect, this reports
+	/// [WsState::Connecting].
+	//
+	pub fn ready_state( &self ) -> WsState
+	{
+		let state  = self.current.ready_state();
+		let shared = self.shared.borrow();
+
+		if state == WsState::Closed && !shared.gave_up && !shared.user_closed
+		{
+			return WsState::Connecting;
+		}
+
+		state
+	}
+
+
+	/// The number of received messages that are buffered, waiting to be consumed, including those of lost
+	/// connections. See [WsStream::queue_len].
+	//
+	pub fn queue_len( &self ) -> usize
+	{
+		self.retired.iter().map( WsStream::queue_len ).sum::<usize>() + self.current.queue_len()
+	}
+
+
+	/// Access the [WsStream] of the current underlying connection.
+	//
+	pub fn current( &self ) -> &WsStream
+	{
+		&self.current
+	}
+
+
+	// Pick up a new connection if one has been established in the mean time. Lost connections that have nothing
+	// left to read are dropped here rather than in poll_next, so they don't pile up when only the Sink is used.
+	//
+	fn install_next( &mut self )
+	{
+		let next = self.shared.borrow_mut().next.take();
+
+		if let Some( next ) = next
+		{
+			let old = std::mem::replace( &mut self.current, next );
+			self.retired.push_back( old );
+		}
+
+		self.retired.retain( |old| !old.is_drained() );
+	}
+
+
+	// Whether we are waiting for a new connection, so messages go to the outbox, if any.
+	//
+	fn reconnecting( &self ) -> bool
+	{
+		!matches!( self.current.ready_state(), WsState::Connecting | WsState::Open )
+	}
+}
+
+
+
+impl fmt::Debug for ReconnectingWsStream
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "ReconnectingWsStream for connection: {}", self.current.wrapped().url() )
+	}
+}
+
+
+
+impl Drop for ReconnectingWsStream
+{
+	// Stop reconnecting. The current connection is closed by the drop of WsStream.
+	//
+	fn drop( &mut self )
+	{
+		self.shared.borrow_mut().user_closed = true;
+		self.abort.abort();
+
+		if let Some( outbox ) = &self.shared.borrow().outbox
+		{
+			outbox.fail( &self.notifier );
+		}
+
+		// The driver no longer forwards the events of the current connection, so let our observers know.
+		//
+		if let WsState::Connecting | WsState::Open = self.current.ready_state()
+		{
+			self.notifier.notify( WsEvent::Closing );
+		}
+	}
+}
+
+
+
+impl Stream for ReconnectingWsStream
+{
+	type Item = WsMessage;
+
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		self.install_next();
+
+		// First drain the connections that were lost.
+		//
+		while let Some( old ) = self.retired.front_mut()
+		{
+			match ready!( Pin::new( old ).poll_next( cx ) )
+			{
+				Some( msg ) => return Some( msg ).into()   ,
+				None        => { self.retired.pop_front(); }
+			}
+		}
+
+		if let Some( msg ) = ready!( Pin::new( &mut self.current ).poll_next( cx ) )
+		{
+			return Some( msg ).into();
+		}
+
+		// The current connection is gone. Wait for a new one unless we gave up.
+		//
+		let mut shared = self.shared.borrow_mut();
+
+		if shared.gave_up
+		{
+			return None.into();
+		}
+
+		shared.waker = Some( cx.waker().clone() );
+
+		Poll::Pending
+	}
+}
+
+
+
+impl Sink<WsMessage> for ReconnectingWsStream
+{
+	type Error = WsErr;
+
+
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.install_next();
+
+		if !self.reconnecting()
+		{
+			return Pin::new( &mut self.current ).poll_ready( cx );
+		}
+
+		let mut shared = self.shared.borrow_mut();
+
+		if shared.gave_up || shared.user_closed
+		{
+			return Err( WsErr::ConnectionNotOpen ).into();
+		}
+
+		if shared.outbox.as_ref().map( Outbox::has_room ).unwrap_or( false )
+		{
+			return Ok(()).into();
+		}
+
+		shared.sink_waker = Some( cx.waker().clone() );
+
+		Poll::Pending
+	}
+
+
+	fn start_send( mut self: Pin<&mut Self>, item: WsMessage ) -> Result<(), Self::Error>
+	{
+		self.install_next();
+
+		if self.reconnecting()
+		{
+			let shared = self.shared.borrow();
+
+			if let ( Some( outbox ), false, false ) = ( &shared.outbox, shared.gave_up, shared.user_closed )
+			{
+				return outbox.push( item );
+			}
+		}
+
+		Pin::new( &mut self.current ).start_send( item )
+	}
+
+
+	// Messages in the outbox count as flushed, like messages handed to the browser without backpressure.
+	//
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		if self.reconnecting() && self.shared.borrow().outbox.is_some()
+		{
+			return Ok(()).into();
+		}
+
+		Pin::new( &mut self.current ).poll_flush( cx )
+	}
+
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		self.shared.borrow_mut().user_closed = true;
+
+		Pin::new( &mut self.current ).poll_close( cx )
+	}
+}
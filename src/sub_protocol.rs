@@ -0,0 +1,58 @@
+/// A sub-protocol that can be offered to the server with [WsConfig::subprotocols](crate::WsConfig::subprotocols).
+/// Usually implemented on an enum of the protocols an application speaks, so the one the server selected can be
+/// obtained as that type with [WsMeta::subprotocol](crate::WsMeta::subprotocol).
+///
+/// ```
+/// use ws_stream_wasm::SubProtocol;
+///
+/// #[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+/// //
+/// enum Chat { V1, V2 }
+///
+/// impl SubProtocol for Chat
+/// {
+///    fn name( &self ) -> &str
+///    {
+///       match self
+///       {
+///          Chat::V1 => "chat.v1",
+///          Chat::V2 => "chat.v2",
+///       }
+///    }
+///
+///    fn from_name( name: &str ) -> Option<Self>
+///    {
+///       [ Chat::V1, Chat::V2 ].iter().copied().find( |p| p.name() == name )
+///    }
+/// }
+///
+/// assert_eq!( Some( Chat::V2 ), Chat::from_name( "chat.v2" ) );
+/// ```
+//
+pub trait SubProtocol: Sized
+{
+	/// The name sent to the server. This must be a token as defined in
+	/// [RFC 7230](https://www.rfc-editor.org/rfc/rfc7230#section-3.2.6), otherwise connecting fails with
+	/// [WsErr::InvalidProtocol](crate::WsErr::InvalidProtocol).
+	//
+	fn name( &self ) -> &str;
+
+	/// The protocol with the name the server selected, `None` if this type doesn't know it.
+	//
+	fn from_name( name: &str ) -> Option<Self>;
+}
+
+
+
+impl SubProtocol for String
+{
+	fn name( &self ) -> &str
+	{
+		self
+	}
+
+	fn from_name( name: &str ) -> Option<Self>
+	{
+		Some( name.to_string() )
+	}
+}
@@ -0,0 +1,484 @@
+use crate::{ import::*, connect_params::ConnectParamsProvider, BoxError, CloseCode, CloseEvent, ConnectParams, Heartbeat, SubProtocol, WsErr, WsMeta, WsStream };
+
+
+/// Configuration for a connection, created with [WsMeta::builder]. This collects all the options that can be
+/// set on a connection in one place. [WsMeta::connect] is a shorthand for a configuration with only protocols set.
+///
+/// ```no_run
+/// use
+/// {
+///    ws_stream_wasm       :: *                        ,
+///    wasm_bindgen_futures :: spawn_local              ,
+///    wasm_bindgen         :: UnwrapThrowExt           ,
+///    std::time            :: Duration                 ,
+/// };
+///
+/// let program = async
+/// {
+///    let (ws, wsio) = WsMeta::builder( "ws://127.0.0.1:3012" )
+///
+///       .protocols       ( vec![ "chat" ]           )
+///       .connect_timeout ( Duration::from_secs( 5 ) )
+///       .receive_capacity( 1024                     )
+///       .drop_close_code ( CloseCode::Normal        )
+///       .connect().await
+///       .expect_throw( "assume the connection succeeds" );
+/// };
+///
+/// spawn_local( program );
+/// ```
+//
+#[ derive( Debug, Clone ) ]
+//
+pub struct WsConfig
+{
+	pub(crate) url              : String                        ,
+	pub(crate) protocols        : Option<Vec<String>>           ,
+	pub(crate) protocol_required: bool                          ,
+	pub(crate) connect_timeout  : Option<Duration>              ,
+	pub(crate) receive_capacity : Option<usize>                 ,
+	pub(crate) overflow_policy  : OverflowPolicy                ,
+	pub(crate) observer_capacity: usize                         ,
+	pub(crate) observer_channel : Channel                       ,
+	pub(crate) drop_close_code  : Option<CloseCode>             ,
+	pub(crate) binary_type      : BinaryType                    ,
+	pub(crate) send_watermarks  : Option<(u32, u32)>            ,
+	pub(crate) drain_interval   : Duration                      ,
+	pub(crate) close_timeout    : Option<Duration>              ,
+	pub(crate) heartbeat        : Option<Heartbeat>             ,
+	pub(crate) idle_timeout     : Option<(Duration, String)>    ,
+	pub(crate) outbox           : Option<(usize, OutboxPolicy)> ,
+	pub(crate) connect_params   : Option<ConnectParamsProvider> ,
+	pub(crate) rejected_codes   : Vec<CloseCode>                ,
+
+	// Set for an attempt that follows one where the server rejected the credentials.
+	//
+	pub(crate) refresh_params: bool,
+
+	// Set by Failover, which drops the attempts that lost on purpose, so that shouldn't log a warning.
+	//
+	pub(crate) cancel_quietly: bool,
+}
+
+
+
+/// What [WsStream] does with an incoming message when its receive buffer is full.
+/// See [WsConfig::receive_capacity] and [WsConfig::overflow_policy].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum OverflowPolicy
+{
+	/// Drop the incoming message. A warning is logged for the first message dropped while the buffer is full.
+	//
+	DropNewest,
+
+	/// Drop the oldest message in the buffer to make room for the incoming one.
+	//
+	DropOldest,
+
+	/// Drop the incoming message and close the connection with the given close code.
+	//
+	Close( CloseCode ),
+
+	/// Drop the incoming message and emit [WsEvent::WsErr](crate::WsEvent::WsErr) with [WsErr::ReceiveQueueFull].
+	/// Observers get one for every dropped message, the receive queue of [WsStream] only one until the next
+	/// message that fits.
+	//
+	Error,
+}
+
+
+
+/// What happens to the messages in the outbox when the connection fails before it opens.
+/// See [WsConfig::outbox].
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+pub enum OutboxPolicy
+{
+	/// Drop the messages and log a warning.
+	//
+	Discard,
+
+	/// Drop the messages and give them to observers with [WsEvent::Undelivered](crate::WsEvent::Undelivered).
+	//
+	Report,
+}
+
+
+
+impl WsConfig
+{
+	/// Create a configuration with default options for a connection to `url`. Equivalent to [WsMeta::builder].
+	//
+	pub fn new( url: impl AsRef<str> ) -> Self
+	{
+		Self
+		{
+			url              : url.as_ref().to_string()    ,
+			protocols        : None                        ,
+			protocol_required: false                       ,
+			connect_timeout  : None                        ,
+			receive_capacity : None                        ,
+			overflow_policy  : OverflowPolicy::DropNewest  ,
+			observer_capacity: 10                          ,
+			observer_channel : Channel::Unbounded          ,
+			drop_close_code  : None                        ,
+			binary_type      : BinaryType::Arraybuffer     ,
+			send_watermarks  : None                        ,
+			drain_interval   : Duration::from_millis( 10 ) ,
+			close_timeout    : None                        ,
+			heartbeat        : None                        ,
+			idle_timeout     : None                        ,
+			outbox           : None                        ,
+			connect_params   : None                        ,
+			rejected_codes   : Vec::new()                  ,
+			refresh_params   : false                       ,
+			cancel_quietly   : false                       ,
+		}
+	}
+
+
+	/// The sub-protocols to offer the server. See [WsMeta::connect].
+	///
+	/// **Note**: Sending protocols to a server that doesn't support them will make the connection fail.
+	//
+	pub fn protocols( mut self, protocols: impl IntoIterator< Item = impl Into<String> > ) -> Self
+	{
+		self.protocols = Some( protocols.into_iter().map( Into::into ).collect() );
+		self
+	}
+
+
+	/// Offer typed sub-protocols to the server. The one it selected can be obtained as `P` with
+	/// [WsMeta::subprotocol]. This replaces any protocols set with [WsConfig::protocols].
+	//
+	pub fn subprotocols<P: SubProtocol>( mut self, protocols: impl IntoIterator< Item = P > ) -> Self
+	{
+		self.protocols = Some( protocols.into_iter().map( |p| p.name().to_string() ).collect() );
+		self
+	}
+
+
+	/// Fail with [WsErr::ProtocolNotNegotiated] when the server accepts the connection without selecting one of the
+	/// offered sub-protocols. The connection is closed in that case. By default, such a connection is accepted and
+	/// [WsMeta::protocol] returns an empty string.
+	///
+	/// Connecting fails with [WsErr::NoProtocolOffered] if no sub-protocols are offered, neither with
+	/// [WsConfig::protocols] or [WsConfig::subprotocols] nor by the provider of [WsConfig::connect_params].
+	//
+	pub fn require_protocol( mut self ) -> Self
+	{
+		self.protocol_required = true;
+		self
+	}
+
+
+	/// Give up if the connection isn't established within `timeout`. In that case the underlying `WebSocket`
+	/// is closed and [WsErr::ConnectTimeout] is returned. By default there is no timeout, so a connection to
+	/// an unresponsive host only fails when the browser gives up, which can take minutes.
+	//
+	pub fn connect_timeout( mut self, timeout: Duration ) -> Self
+	{
+		self.connect_timeout = Some( timeout );
+		self
+	}
+
+
+	/// The maximum number of received messages [WsStream] will buffer while they aren't consumed. What happens when
+	/// the buffer is full is determined by [WsConfig::overflow_policy]. By default the buffer is unbounded.
+	//
+	pub fn receive_capacity( mut self, capacity: usize ) -> Self
+	{
+		self.receive_capacity = Some( capacity );
+		self
+	}
+
+
+	/// What to do with an incoming message when the receive buffer is full. Only relevant with
+	/// [WsConfig::receive_capacity]. Defaults to [OverflowPolicy::DropNewest].
+	///
+	/// [OverflowPolicy::Close] only accepts the close codes that [WsConfig::drop_close_code] accepts.
+	//
+	pub fn overflow_policy( mut self, policy: OverflowPolicy ) -> Self
+	{
+		self.overflow_policy = policy;
+		self
+	}
+
+
+	/// The initial capacity of the storage for observers of [WsMeta]. Corresponds to
+	/// [`pharos::Pharos::new`](https://docs.rs/pharos/0.5/pharos/struct.Pharos.html#method.new). Defaults to 10.
+	//
+	pub fn observer_capacity( mut self, capacity: usize ) -> Self
+	{
+		self.observer_capacity = capacity;
+		self
+	}
+
+
+	/// The pharos channel of the observers _ws_stream_wasm_ creates itself: to wait for the connection to open, for
+	/// [WsStream], [WsMeta::state_watch], [WsMeta::closed] and, with [ReconnectingWsMeta](crate::ReconnectingWsMeta),
+	/// to forward the events of every connection. Observers added with `Observable::observe` use the channel of
+	/// their `ObserveConfig`. Defaults to `Channel::Unbounded`.
+	///
+	/// While the channel of such an observer is full, events are held back until it has room, also for all other
+	/// observers, so they keep their order. [WsStream] keeps consuming its events, but with a small bound, a slow
+	/// consumer of [WsMeta::state_watch] delays event delivery. A bounded channel must have room for at least one
+	/// event.
+	//
+	pub fn observer_channel( mut self, channel: Channel ) -> Self
+	{
+		self.observer_channel = channel;
+		self
+	}
+
+
+	/// The close code to use when [WsStream] closes the connection by itself, that is when it is dropped or when
+	/// `Sink::poll_close` is called. By default no code is sent, which the browser reports as `1005`.
+	///
+	/// Browsers only accept some codes, see [CloseCode::is_allowed]. Other codes make
+	/// [connect](WsConfig::connect) return [WsErr::InvalidCloseCode].
+	//
+	pub fn drop_close_code( mut self, code: impl Into<CloseCode> ) -> Self
+	{
+		self.drop_close_code = Some( code.into() );
+		self
+	}
+
+
+	/// The binary type of the connection. Defaults to `Arraybuffer`.
+	///
+	/// With [`BinaryType::Blob`], the browser might keep large payloads off the JavaScript heap. The `Stream`
+	/// impl of [WsStream] reads the data of each blob asynchronously, still yielding messages in the order they
+	/// arrived. [WsStream::js_messages] yields the blobs themselves as [JsMessage::Blob](crate::JsMessage::Blob).
+	//
+	pub fn binary_type( mut self, binary_type: BinaryType ) -> Self
+	{
+		self.binary_type = binary_type;
+		self
+	}
+
+
+	/// Enable backpressure on the `Sink` impl of [WsStream], based on the number of bytes the browser has
+	/// buffered but not yet transmitted
+	/// ([`bufferedAmount`](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/bufferedAmount)).
+	///
+	/// Once more than `high` bytes are buffered, `poll_ready` returns `Pending` until the buffer drains to `low`
+	/// bytes or less. `poll_flush` resolves once everything has been transmitted. Note that `SinkExt::send` flushes
+	/// after every message, so use `feed` or `send_all` to keep the buffer filled.
+	///
+	/// By default there is no backpressure and a producer can fill the browser's buffer without bounds. If `low` is
+	/// bigger than `high`, `high` is used for both.
+	//
+	pub fn send_watermarks( mut self, high: u32, low: u32 ) -> Self
+	{
+		self.send_watermarks = Some(( high, low.min( high ) ));
+		self
+	}
+
+
+	/// The browser has no event to signal that its send buffer has drained, so while waiting for it, [WsStream]
+	/// checks `bufferedAmount` at this interval. Only relevant with [WsConfig::send_watermarks]. Defaults to 10ms.
+	//
+	pub fn drain_interval( mut self, interval: Duration ) -> Self
+	{
+		self.drain_interval = interval;
+		self
+	}
+
+
+	/// How long to wait for the closing handshake to complete after closing through [WsMeta] or `Sink::poll_close`
+	/// on [WsStream]. If the browser hasn't reported the connection as closed by then, observers receive a
+	/// [WsEvent::Closed](crate::WsEvent::Closed) with code [CloseCode::Abnormal] and `was_clean: false`, and the
+	/// close methods resolve with that event. A close event the browser reports later is ignored.
+	///
+	/// By default there is no timeout, so closing hangs for as long as the server doesn't complete the handshake.
+	//
+	pub fn close_timeout( mut self, timeout: Duration ) -> Self
+	{
+		self.close_timeout = Some( timeout );
+		self
+	}
+
+
+	/// Send a ping message at an interval and close the connection when the server stops answering. See
+	/// [Heartbeat]. By default there is no heartbeat.
+	///
+	/// The close code of the heartbeat is validated like [WsConfig::drop_close_code].
+	//
+	pub fn heartbeat( mut self, heartbeat: Heartbeat ) -> Self
+	{
+		self.heartbeat = Some( heartbeat );
+		self
+	}
+
+
+	/// Close the connection with [CloseCode::Normal] and `reason` once no message has been sent or received for
+	/// `timeout`. Observers receive [WsEvent::IdleTimeout](crate::WsEvent::IdleTimeout) before the connection
+	/// closes. The messages of a [Heartbeat] don't count as activity. By default connections are never closed for
+	/// being idle.
+	///
+	/// A reason longer than 123 bytes makes [connect](WsConfig::connect) return [WsErr::ReasonStringToLong].
+	//
+	pub fn idle_timeout( mut self, timeout: Duration, reason: impl Into<String> ) -> Self
+	{
+		self.idle_timeout = Some(( timeout, reason.into() ));
+		self
+	}
+
+
+	/// Accept up to `capacity` messages while the connection is connecting, instead of making `Sink::poll_ready`
+	/// wait and [WsSender::try_send](crate::WsSender::try_send) fail. The messages are sent in order as soon as the
+	/// connection opens, before any message sent after that. This is mostly useful with
+	/// [WsMeta::from_websocket_config], where the stream is available before the connection opens.
+	///
+	/// When the outbox is full, `Sink::poll_ready` waits for the connection to open and `start_send` returns
+	/// [WsErr::OutboxFull]. When the connection fails before opening, or the stream is dropped while connecting,
+	/// the queued messages are handled according to `policy`. By default there is no outbox.
+	///
+	/// With [ReconnectingWsMeta::connect_with](crate::ReconnectingWsMeta::connect_with), this also accepts
+	/// messages while reconnecting, see [ReconnectingWsStream](crate::ReconnectingWsStream).
+	//
+	pub fn outbox( mut self, capacity: usize, policy: OutboxPolicy ) -> Self
+	{
+		self.outbox = Some(( capacity, policy ));
+		self
+	}
+
+
+	/// Call `provider` before every `WebSocket` is created, eg. to put a fresh authentication token in the query
+	/// string or in a sub-protocol. This also happens for every attempt of [ReconnectingWsMeta](crate::ReconnectingWsMeta)
+	/// and [Failover](crate::Failover).
+	///
+	/// The provider receives the url and sub-protocols of this configuration, and returns the ones to use. When it
+	/// returns an error, connecting fails with [WsErr::ConnectParams], from which the error can be downcast to its
+	/// original type. The configuration is validated before the provider is called, and the sub-protocols it returns
+	/// are validated again.
+	///
+	/// ```
+	/// use ws_stream_wasm::*;
+	///
+	/// let config = WsMeta::builder( "wss://example.com/chat" )
+	///
+	///    .credentials_rejected_on( vec![ CloseCode::Application( 4001 ) ] )
+	///
+	///    .connect_params( |mut params: ConnectParams| async move
+	///    {
+	///       // Fetch a new token when params.refresh() is true, otherwise a cached one can be used.
+	///       //
+	///       params.set_query( "token", "secret" );
+	///       Ok( params )
+	///    })
+	/// ;
+	/// ```
+	//
+	pub fn connect_params<F, Fut>( mut self, provider: F ) -> Self
+
+		where F  : Fn( ConnectParams ) -> Fut + 'static                          ,
+		      Fut: Future< Output = Result<ConnectParams, BoxError> > + 'static ,
+	{
+		self.connect_params = Some( ConnectParamsProvider::new( provider ) );
+		self
+	}
+
+
+	/// Close codes with which the server tells that it rejected the credentials of the connection, eg. an expired
+	/// token. When a connection closes or fails with one of these, the next attempt of
+	/// [ReconnectingWsMeta](crate::ReconnectingWsMeta) or [Failover](crate::Failover) asks the provider set with
+	/// [WsConfig::connect_params] for fresh credentials through [ConnectParams::refresh].
+	///
+	/// Only a server that accepts the connection and then closes it can give a code. When the opening handshake
+	/// fails, eg. because the server answers with `401 Unauthorized`, browsers report [CloseCode::Abnormal]
+	/// whatever the reason. As [Failover](crate::Failover) only sees connections that failed to open, it can only
+	/// recognize a rejection when [CloseCode::Abnormal] is listed here, at the cost of also refreshing after
+	/// network errors.
+	//
+	pub fn credentials_rejected_on( mut self, codes: impl IntoIterator< Item = impl Into<CloseCode> > ) -> Self
+	{
+		self.rejected_codes = codes.into_iter().map( Into::into ).collect();
+		self
+	}
+
+
+	// Whether the server rejected our credentials when closing with this event.
+	//
+	pub(crate) fn rejects_credentials( &self, evt: &CloseEvent ) -> bool
+	{
+		self.rejected_codes.contains( &evt.close_code() )
+	}
+
+
+	/// Connect to the server with this configuration. See [WsMeta::connect] for more information.
+	//
+	pub async fn connect( self ) -> Result< (WsMeta, WsStream), WsErr >
+	{
+		WsMeta::connect_config( self ).await
+	}
+
+
+	// Verify the options the browser would reject once we use them.
+	//
+	pub(crate) fn validate( &self ) -> Result<(), WsErr>
+	{
+		let close_codes = self.drop_close_code.into_iter()
+
+			.chain( match self.overflow_policy
+			{
+				OverflowPolicy::Close( code ) => Some( code ),
+				_                             => None        ,
+			})
+
+			.chain( self.heartbeat.as_ref().map( Heartbeat::get_close_code ) )
+		;
+
+		for code in close_codes
+		{
+			code.validate()?;
+		}
+
+		if self.idle_timeout.as_ref().map( |(_, reason)| reason.len() > 123 ).unwrap_or( false )
+		{
+			return Err( WsErr::ReasonStringToLong );
+		}
+
+		if self.observer_channel == Channel::Bounded( 0 )
+		{
+			return Err( WsErr::InvalidObserverChannel );
+		}
+
+		// A provider can still add the protocols. Once it has, this is checked again without it.
+		//
+		let offered = self.protocols.as_deref().unwrap_or_default();
+
+		if self.protocol_required && self.connect_params.is_none() && offered.is_empty()
+		{
+			return Err( WsErr::NoProtocolOffered );
+		}
+
+		// The browser throws a SyntaxError for these, just like for an invalid url, so we check them ourselves
+		// to tell them apart.
+		//
+		for (i, proto) in self.protocols.iter().flatten().enumerate()
+		{
+			let duplicate = self.protocols.iter().flatten().take( i ).any( |p| p == proto );
+
+			if duplicate || !is_token( proto )
+			{
+				return Err( WsErr::InvalidProtocol{ supplied: proto.clone() } );
+			}
+		}
+
+		Ok(())
+	}
+}
+
+
+
+// Whether `s` is a token as defined in RFC 7230, which sub-protocol names must be.
+//
+fn is_token( s: &str ) -> bool
+{
+	!s.is_empty() && s.chars().all( |c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains( c ) )
+}
@@ -1,9 +1,9 @@
-use crate::{ import::*, WsErr };
+use crate::{ import::*, CloseCode, WsErr, WsMessage };
 
 
 /// Events related to the WebSocket. You can filter like:
 ///
-/// ```
+/// ```no_run
 /// use
 ///{
 ///   ws_stream_wasm       :: *                        ,
@@ -65,7 +65,48 @@ pub enum WsEvent
 	/// when an incoming message can not be converted to Rust types, eg. a String message with invalid
 	/// encoding.
 	//
-	WsErr( WsErr )
+	WsErr( WsErr ),
+
+	/// Only emitted by [ReconnectingWsMeta](crate::ReconnectingWsMeta). The connection was lost and attempt
+	/// number `attempt` (starting at 1) to re-establish it has been scheduled.
+	//
+	Reconnecting
+	{
+		/// The number of the attempt, starting at 1. Reset after every successful reconnection.
+		//
+		attempt: u32
+	},
+
+	/// Only emitted by [ReconnectingWsMeta](crate::ReconnectingWsMeta). A lost connection has been
+	/// re-established and is ready for use.
+	//
+	Reconnected,
+
+	/// Only emitted with a [Heartbeat](crate::Heartbeat). A pong has been received.
+	//
+	Pong
+	{
+		/// The time between sending the ping and receiving the pong.
+		//
+		rtt: Duration
+	},
+
+	/// Only emitted with a [Heartbeat](crate::Heartbeat). No pong has been received in time, so the connection
+	/// is considered dead and will be closed.
+	//
+	HeartbeatTimeout,
+
+	/// Only emitted with [WsConfig::idle_timeout](crate::WsConfig::idle_timeout). No message has been sent or
+	/// received for the configured time, so the connection will be closed with [CloseCode::Normal].
+	//
+	IdleTimeout,
+
+	/// Only emitted with [OutboxPolicy::Report](crate::OutboxPolicy::Report). The connection failed before it
+	/// opened, or a [ReconnectingWsStream](crate::ReconnectingWsStream) stopped reconnecting, so the messages
+	/// queued in the outbox were never sent. They are given back in the order they were queued, so they can be
+	/// sent on another connection.
+	//
+	Undelivered( Vec<WsMessage> ),
 }
 
 
@@ -110,6 +151,54 @@ impl WsEvent
 	{
 		matches!( self, Self::WsErr(_) )
 	}
+
+	/// Predicate indicating whether this is a [WsEvent::Reconnecting] event. Can be used as a filter for the
+	/// event stream obtained with [`pharos::Observable::observe`] on [`ReconnectingWsMeta`](crate::ReconnectingWsMeta).
+	//
+	pub fn is_reconnecting( &self ) -> bool
+	{
+		matches!( self, Self::Reconnecting{..} )
+	}
+
+	/// Predicate indicating whether this is a [WsEvent::Reconnected] event. Can be used as a filter for the
+	/// event stream obtained with [`pharos::Observable::observe`] on [`ReconnectingWsMeta`](crate::ReconnectingWsMeta).
+	//
+	pub fn is_reconnected( &self ) -> bool
+	{
+		matches!( self, Self::Reconnected )
+	}
+
+	/// Predicate indicating whether this is a [WsEvent::Pong] event. Can be used as a filter for the
+	/// event stream obtained with [`pharos::Observable::observe`] on [`WsMeta`](crate::WsMeta).
+	//
+	pub fn is_pong( &self ) -> bool
+	{
+		matches!( self, Self::Pong{..} )
+	}
+
+	/// Predicate indicating whether this is a [WsEvent::HeartbeatTimeout] event. Can be used as a filter for the
+	/// event stream obtained with [`pharos::Observable::observe`] on [`WsMeta`](crate::WsMeta).
+	//
+	pub fn is_heartbeat_timeout( &self ) -> bool
+	{
+		matches!( self, Self::HeartbeatTimeout )
+	}
+
+	/// Predicate indicating whether this is a [WsEvent::IdleTimeout] event. Can be used as a filter for the
+	/// event stream obtained with [`pharos::Observable::observe`] on [`WsMeta`](crate::WsMeta).
+	//
+	pub fn is_idle_timeout( &self ) -> bool
+	{
+		matches!( self, Self::IdleTimeout )
+	}
+
+	/// Predicate indicating whether this is a [WsEvent::Undelivered] event. Can be used as a filter for the
+	/// event stream obtained with [`pharos::Observable::observe`] on [`WsMeta`](crate::WsMeta).
+	//
+	pub fn is_undelivered( &self ) -> bool
+	{
+		matches!( self, Self::Undelivered(_) )
+	}
 }
 
 
@@ -123,7 +212,7 @@ impl WsEvent
 //
 pub struct CloseEvent
 {
-	/// The close code.
+	/// The close code. See [CloseEvent::close_code] for a typed version.
 	/// See: [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close).
 	//
 	pub code: u16,
@@ -137,20 +226,132 @@ pub struct CloseEvent
 	/// See: [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close).
 	//
 	pub was_clean: bool,
+
+	/// Who closed the connection, or whether it was lost.
+	//
+	pub initiator: CloseInitiator,
 }
 
 
-impl From<JsCloseEvt> for CloseEvent
+
+/// Who closed a connection. The browser doesn't tell, so _ws_stream_wasm_ remembers why it closed the connection
+/// itself, and otherwise looks at the close code and at
+/// [`navigator.onLine`](https://developer.mozilla.org/en-US/docs/Web/API/Navigator/onLine) when the connection closed.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq, Hash ) ]
+//
+pub enum CloseInitiator
 {
-	fn from( js_evt: JsCloseEvt ) -> Self
+	/// The connection was closed from this side, through [WsMeta](crate::WsMeta), or by dropping or closing
+	/// [WsStream](crate::WsStream).
+	//
+	Local,
+
+	/// The heartbeat got no pong in time, so _ws_stream_wasm_ closed the connection because it considers it lost.
+	//
+	Heartbeat,
+
+	/// The server closed the connection with a closing handshake.
+	//
+	Remote,
+
+	/// The connection was lost without a closing handshake while the browser considered itself online,
+	/// eg. the server crashed, a proxy dropped the connection or the connection couldn't be established.
+	//
+	Abnormal,
+
+	/// The connection was lost without a closing handshake while the browser considered itself offline.
+	//
+	NetworkLoss,
+}
+
+
+
+impl CloseInitiator
+{
+	fn detect( local: Option<CloseInitiator>, code: u16 ) -> Self
+	{
+		if let Some( initiator ) = local
+		{
+			return initiator;
+		}
+
+		match CloseCode::from( code )
+		{
+			CloseCode::Abnormal if !on_line() => CloseInitiator::NetworkLoss ,
+			CloseCode::Abnormal               => CloseInitiator::Abnormal    ,
+			_                                 => CloseInitiator::Remote      ,
+		}
+	}
+}
+
+
+
+// Whether the browser considers itself online. We use the global object rather than `window`, so this also
+// works in workers. Assume we are online if the browser doesn't tell.
+//
+fn on_line() -> bool
+{
+	Reflect::get( &js_sys::global(), &"navigator".into() )
+
+		.and_then( |nav| Reflect::get( &nav, &"onLine".into() ) )
+		.ok()
+		.and_then( |on_line| on_line.as_bool() )
+		.unwrap_or( true )
+}
+
+
+impl CloseEvent
+{
+	/// Convert the browser event. `local` tells why we closed the connection ourselves, if we did.
+	//
+	pub(crate) fn from_js( js_evt: &JsCloseEvt, local: Option<CloseInitiator> ) -> Self
 	{
 		Self
 		{
-			code     : js_evt.code     () ,
-			reason   : js_evt.reason   () ,
-			was_clean: js_evt.was_clean() ,
+			code     : js_evt.code()                                  ,
+			reason   : js_evt.reason()                                ,
+			was_clean: js_evt.was_clean()                             ,
+			initiator: CloseInitiator::detect( local, js_evt.code() ) ,
 		}
 	}
+
+
+	/// The close code as a [CloseCode].
+	//
+	pub fn close_code( &self ) -> CloseCode
+	{
+		self.code.into()
+	}
+
+
+	/// Whether the connection was closed cleanly with [CloseCode::Normal].
+	//
+	pub fn is_normal( &self ) -> bool
+	{
+		self.was_clean && self.close_code() == CloseCode::Normal
+	}
+
+
+	/// Whether the connection was lost without a close frame ([CloseCode::Abnormal]), eg. because the network
+	/// failed or the server couldn't be reached.
+	//
+	pub fn is_abnormal( &self ) -> bool
+	{
+		self.close_code() == CloseCode::Abnormal
+	}
+}
+
+
+/// Without knowing whether the connection was closed locally, [CloseEvent::initiator] is never
+/// [CloseInitiator::Local] nor [CloseInitiator::Heartbeat].
+//
+impl From<JsCloseEvt> for CloseEvent
+{
+	fn from( js_evt: JsCloseEvt ) -> Self
+	{
+		Self::from_js( &js_evt, None )
+	}
 }
 
 
@@ -0,0 +1,68 @@
+use crate::{ import::*, ws_reader::Incoming, WsEvent, WsMessage, WsReader };
+
+
+/// An item of [WsItems]: an incoming message or an event of the connection.
+//
+#[ derive( Debug, Clone, PartialEq, Eq ) ]
+//
+pub enum WsItem
+{
+	/// A message was received.
+	//
+	Message( WsMessage ),
+
+	/// An event happened on the connection. These are the same events observers of [WsMeta](crate::WsMeta)
+	/// receive.
+	//
+	Event( WsEvent ),
+}
+
+
+
+/// A `Stream` of [WsItem] borrowing a [WsStream](crate::WsStream) or a [WsReader]. Created with
+/// [WsStream::items](crate::WsStream::items) or [WsReader::items].
+///
+/// Messages and events are yielded in the order the browser reported them, so the last message before
+/// [WsEvent::Closed] is the last message received on the connection. Events that happened before the
+/// [WsStream](crate::WsStream) was created are not included, eg. [WsEvent::Open] with
+/// [WsMeta::connect](crate::WsMeta::connect). The stream ends after [WsEvent::Closed].
+///
+/// Messages and events are taken from the same queue as the `Stream` impl of [WsStream](crate::WsStream), which
+/// skips events.
+///
+/// Events that repeat while the queue isn't read are coalesced, so they can't fill it up: between two messages
+/// there is at most one [WsErr::ReceiveQueueFull](crate::WsErr::ReceiveQueueFull), however many messages were
+/// dropped, and at most one [WsEvent::Pong], which has the round trip time of the latest pong. Observers of
+/// [WsMeta](crate::WsMeta) still receive every event.
+//
+#[ derive( Debug ) ]
+//
+pub struct WsItems<'a>
+{
+	inner: &'a mut WsReader,
+}
+
+
+impl<'a> WsItems<'a>
+{
+	pub(crate) fn new( inner: &'a mut WsReader ) -> Self
+	{
+		Self { inner }
+	}
+}
+
+
+impl Stream for WsItems<'_>
+{
+	type Item = WsItem;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		Poll::Ready( match ready!( self.inner.poll_next_incoming( cx, true, true ) )
+		{
+			Some( Incoming::Message( msg ) ) => Some( WsItem::Message( WsMessage::try_from( msg ).expect_throw( "blobs have been read" ) ) ),
+			Some( Incoming::Event  ( evt ) ) => Some( WsItem::Event( evt ) ),
+			None                             => None,
+		})
+	}
+}
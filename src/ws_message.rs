@@ -1,4 +1,4 @@
-use crate::{ import::*, WsErr };
+use crate::{ import::*, ws_reader::Incoming, CloseInitiator, WsErr, WsEvent, JsMessage, WsReader };
 
 
 /// Represents a WebSocket Message, after converting from JavaScript type.
@@ -20,7 +20,7 @@ pub enum WsMessage
 
 /// This will convert the JavaScript event into a WsMessage. Note that this
 /// will only work if the connection is set to use the binary type ArrayBuffer.
-/// On binary type Blob, this will panic.
+/// On binary type Blob, this will return [WsErr::CantDecodeBlob].
 //
 impl TryFrom< MessageEvent > for WsMessage
 {
@@ -28,47 +28,7 @@ impl TryFrom< MessageEvent > for WsMessage
 
 	fn try_from( evt: MessageEvent ) -> Result< Self, Self::Error >
 	{
-		match evt.data()
-		{
-			d if d.is_instance_of::< ArrayBuffer >() =>
-			{
-				let     buffy = Uint8Array::new( d.unchecked_ref() );
-				let mut v     = vec![ 0; buffy.length() as usize ];
-
-				buffy.copy_to( &mut v ); // FIXME: get rid of this copy
-
-				Ok( WsMessage::Binary( v ) )
-			}
-
-
-			// We don't allow invalid encodings. In principle if needed,
-			// we could add a variant to WsMessage with a CString or an OsString
-			// to allow the user to access this data. However until there is a usecase,
-			// I'm not inclined, amongst other things because the conversion from Js isn't very
-			// clear and it would require a bunch of testing for something that's a rather bad
-			// idea to begin with. If you need data that is not a valid string, use a binary
-			// message.
-			//
-			d if d.is_string() =>
-			{
-				match d.as_string()
-				{
-					Some(text) => Ok ( WsMessage::Text( text ) ),
-					None       => Err( WsErr::InvalidEncoding  ),
-				}
-			}
-
-
-			// We have set the binary mode to array buffer (WsMeta::connect), so normally this shouldn't happen.
-			// That is as long as this is used within the context of the WsMeta constructor.
-			//
-			d if d.is_instance_of::< Blob >() => Err( WsErr::CantDecodeBlob ),
-
-
-			// should never happen.
-			//
-			_ => Err( WsErr::UnknownDataType ),
-		}
+		JsMessage::try_from( evt )?.try_into()
 	}
 }
 
@@ -118,3 +78,66 @@ impl AsRef<[u8]> for WsMessage
 
 
 
+
+
+
+/// A `Stream` of `Result<WsMessage, WsErr>` borrowing a [WsStream](crate::WsStream) or a [WsReader]. Created with
+/// [WsStream::try_messages](crate::WsStream::try_messages) or [WsReader::try_messages].
+///
+/// Incoming messages that can't be decoded are yielded as errors in the position where they were received, where
+/// the `Stream` impl of [WsStream](crate::WsStream) skips them. This goes for all errors observers receive as
+/// [WsEvent::WsErr], eg. [WsErr::ReceiveQueueFull] takes the place of the dropped message.
+///
+/// Unlike the `Stream` impl of [WsStream](crate::WsStream), which ends when the connection starts closing, this
+/// only ends once the connection is closed. When the connection closed without a closing handshake and we didn't
+/// close it ourselves, the last item before `None` is [WsErr::ConnectionLost] with the close event. So is a close
+/// after a [heartbeat](crate::WsConfig::heartbeat) timeout, whether or not the handshake completed. A close from
+/// this side that didn't complete the handshake, eg. because [WsConfig::close_timeout](crate::WsConfig::close_timeout)
+/// expired, ends the stream without error, as the connection was closed on purpose.
+//
+#[ derive( Debug ) ]
+//
+pub struct TryMessages<'a>
+{
+	inner: &'a mut WsReader,
+}
+
+
+impl<'a> TryMessages<'a>
+{
+	pub(crate) fn new( inner: &'a mut WsReader ) -> Self
+	{
+		Self { inner }
+	}
+}
+
+
+impl Stream for TryMessages<'_>
+{
+	type Item = Result< WsMessage, WsErr >;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		loop
+		{
+			return match ready!( self.inner.poll_next_incoming( cx, true, true ) )
+			{
+				Some( Incoming::Message( msg                     ) ) => Some( WsMessage::try_from( msg ) ).into(),
+				Some( Incoming::Event  ( WsEvent::WsErr( err )   ) ) => Some( Err( err ) ).into(),
+
+				// When we closed ourselves, the close handshake not finishing doesn't mean anything was lost, unless
+				// we closed because the heartbeat considered the connection lost.
+				//
+				Some( Incoming::Event( WsEvent::Closed( event ) ) )
+
+					if event.initiator == CloseInitiator::Heartbeat
+					|| ( !event.was_clean && event.initiator != CloseInitiator::Local ) =>
+
+					Some( Err( WsErr::ConnectionLost{ event } ) ).into(),
+
+				Some( Incoming::Event( _ ) ) => continue,
+				None                         => None.into(),
+			}
+		}
+	}
+}
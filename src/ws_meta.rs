@@ -1,4 +1,4 @@
-use crate::{ import::*, WsErr, WsState, WsStream, WsEvent, CloseEvent, notify };
+use crate::{ import::*, delay::Delay, notifier::Notifier, CloseCode, WsConfig, WsErr, WsState, WsStateWatch, WsStream, WsEvent, CloseEvent, CloseInitiator, ConnectParams, SubProtocol, UrlError };
 
 
 /// The meta data related to a websocket. Allows access to the methods on the WebSocket API.
@@ -16,8 +16,13 @@ use crate::{ import::*, WsErr, WsState, WsStream, WsEvent, CloseEvent, notify };
 //
 pub struct WsMeta
 {
-	ws    : SendWrapper< Rc<WebSocket> > ,
-	pharos: SharedPharos<WsEvent>        ,
+	ws           : SendWrapper< Rc<WebSocket> > ,
+	notifier     : Notifier                     ,
+	close_timeout: Option<Duration>             ,
+
+	// Set when we close the connection ourselves, so the close event can tell who closed it.
+	//
+	local_close: SendWrapper< Rc<Cell< Option<CloseInitiator> >> >,
 }
 
 
@@ -38,112 +43,125 @@ impl WsMeta
 	/// Browsers will forbid making websocket connections to certain ports. See this [Stack Overflow question](https://stackoverflow.com/questions/4313403/why-do-browsers-block-some-ports/4314070).
 	/// `connect` will return a [WsErr::ConnectionFailed] as it is indistinguishable from other connection failures.
 	///
-	/// If the URL is invalid, a [WsErr::InvalidUrl] is returned. See the [HTML Living Standard](https://html.spec.whatwg.org/multipage/web-sockets.html#dom-websocket) for more information.
+	/// If the URL is invalid, a [WsErr::InvalidUrl] is returned, with a [UrlError] telling what is wrong. See the [HTML Living Standard](https://html.spec.whatwg.org/multipage/web-sockets.html#dom-websocket) for more information.
 	///
 	/// When the connection fails (server port not open, wrong ip, wss:// on ws:// server, ... See the [HTML Living Standard](https://html.spec.whatwg.org/multipage/web-sockets.html#dom-websocket)
 	/// for details on all failure possibilities), a [WsErr::ConnectionFailed] is returned.
 	///
 	/// **Note**: Sending protocols to a server that doesn't support them will make the connection fail.
+	///
+	/// ## Urls
+	///
+	/// Besides `ws://` and `wss://` urls, `url` can be an `http://` or `https://` url or a url relative to the
+	/// location of the current document or worker, eg. `/chat`. It's resolved against that location and the scheme
+	/// is changed to `ws` or `wss` respectively, so `/chat` on a page served over https connects to `wss://host/chat`.
+	///
+	/// To set other options on the connection, use [WsMeta::builder].
 	//
 	pub async fn connect( url: impl AsRef<str>, protocols: impl Into<Option<Vec<&str>>> )
 
 		-> Result< (Self, WsStream), WsErr >
 	{
-		let res = match protocols.into()
+		let mut config = Self::builder( url );
+
+		if let Some( protocols ) = protocols.into()
 		{
-			None => WebSocket::new( url.as_ref() ),
+			config = config.protocols( protocols );
+		}
 
-			Some(v) =>
-			{
-				let js_protos = v.iter().fold( Array::new(), |acc, proto|
-				{
-					acc.push( &JsValue::from_str( proto ) );
-					acc
-				});
+		config.connect().await
+	}
 
-				WebSocket::new_with_str_sequence( url.as_ref(), &js_protos )
-			}
-		};
 
 
-		// Deal with errors from the WebSocket constructor.
-		//
-		let ws = match res
-		{
-			Ok(ws) => SendWrapper::new( Rc::new( ws ) ),
+	/// Create a [WsConfig] to set options on a connection before connecting with [WsConfig::connect].
+	//
+	pub fn builder( url: impl AsRef<str> ) -> WsConfig
+	{
+		WsConfig::new( url )
+	}
 
-			Err(e) =>
-			{
-				let de: &DomException = e.unchecked_ref();
 
-				match de.code()
-				{
-					DomException::SYNTAX_ERR =>
 
-						return Err( WsErr::InvalidUrl{ supplied: url.as_ref().to_string() } ),
+	/// Like [WsMeta::connect], but gives up if the connection isn't established within `timeout`. In that case the
+	/// underlying `WebSocket` is closed and [WsErr::ConnectTimeout] is returned. See [WsConfig::connect_timeout].
+	//
+	pub async fn connect_timeout( url: impl AsRef<str>, protocols: impl Into<Option<Vec<&str>>>, timeout: Duration )
 
+		-> Result< (Self, WsStream), WsErr >
+	{
+		let mut config = Self::builder( url ).connect_timeout( timeout );
 
-					_ => unreachable!(),
-				};
-			}
-		};
+		if let Some( protocols ) = protocols.into()
+		{
+			config = config.protocols( protocols );
+		}
 
+		config.connect().await
+	}
 
-		// Create our pharos.
-		//
-		let mut pharos = SharedPharos::default();
-		let     ph1    = pharos.clone();
-		let     ph2    = pharos.clone();
-		let     ph3    = pharos.clone();
-		let     ph4    = pharos.clone();
 
 
-		// Setup our event listeners
-		//
-		#[ allow( trivial_casts ) ]
-		//
-		let on_open = Closure::wrap( Box::new( move ||
-		{
-			// notify observers
-			//
-			notify( ph1.clone(), WsEvent::Open )
+	pub(crate) async fn connect_config( mut config: WsConfig ) -> Result< (Self, WsStream), WsErr >
+	{
+		config.validate()?;
 
+		if let Some( provider ) = config.connect_params.take()
+		{
+			let params = ConnectParams::new
+			(
+				resolve_url( &config.url )?,
+				config.protocols.clone().unwrap_or_default(),
+				config.refresh_params,
+			);
 
-		}) as Box< dyn FnMut() > );
+			let (url, protocols) = provider.provide( params ).await?.into_parts();
 
+			config.url       = url;
+			config.protocols = Some( protocols ).filter( |p| !p.is_empty() );
 
-		// TODO: is there no information at all in an error?
-		//
-		#[ allow( trivial_casts ) ]
-		//
-		let on_error = Closure::wrap( Box::new( move ||
-		{
-			// notify observers.
+			// The provider might have put a credential in a sub-protocol that isn't a valid token, or not offered
+			// the protocols required.
 			//
-			notify( ph2.clone(), WsEvent::Error )
+			config.validate()?;
+		}
 
-		}) as Box< dyn FnMut() > );
 
+		let url = resolve_url( &config.url )?;
+		let url = url.as_str();
 
-		#[ allow( trivial_casts ) ]
-		//
-		let on_close = Closure::wrap( Box::new( move |evt: JsCloseEvt|
+		let res = match &config.protocols
 		{
-			let c = WsEvent::Closed( CloseEvent
+			None => WebSocket::new( url ),
+
+			Some(v) =>
 			{
-				code     : evt.code()     ,
-				reason   : evt.reason()   ,
-				was_clean: evt.was_clean(),
-			});
+				let js_protos = v.iter().fold( Array::new(), |acc, proto|
+				{
+					acc.push( &JsValue::from_str( proto ) );
+					acc
+				});
 
-			notify( ph3.clone(), c )
+				WebSocket::new_with_str_sequence( url, &js_protos )
+			}
+		};
+
+
+		// Deal with errors from the WebSocket constructor.
+		//
+		let ws = match res
+		{
+			Ok(ws) => SendWrapper::new( Rc::new( ws ) ),
 
-		}) as Box< dyn FnMut( JsCloseEvt ) > );
+			Err(e) => return Err( constructor_error( e, &config.url ) ),
+		};
 
 
-		ws.set_onopen ( Some( on_open .as_ref().unchecked_ref() ));
-		ws.set_onclose( Some( on_close.as_ref().unchecked_ref() ));
-		ws.set_onerror( Some( on_error.as_ref().unchecked_ref() ));
+		// Create our pharos.
+		//
+		let notifier    = Notifier::new( config.observer_capacity, config.observer_channel );
+		let local_close = SendWrapper::new( Rc::new( Cell::new( None ) ) );
+		let (on_open, on_error, on_close) = install_callbacks( &ws, &notifier, &local_close );
 
 		// In case of future task cancellation the current task may be interrupted at an await, therefore not reaching
 		// the `WsStream` construction, whose `Drop` glue would have been responsible for unregistering the callbacks.
@@ -152,71 +170,172 @@ impl WsMeta
 		//
 		let guard =
 		{
-			struct Guard<'lt> { ws: &'lt WebSocket }
+			struct Guard<'lt> { ws: &'lt WebSocket, quiet: bool }
 
 			impl Drop for Guard<'_>
 			{
 				fn drop(&mut self)
 				{
-					self.ws.set_onopen(None);
-					self.ws.set_onclose(None);
-					self.ws.set_onerror(None);
-					self.ws.close().unwrap_throw(); // cannot throw without code and reason.
+					abort_connect( self.ws );
 
-					log::warn!( "WsMeta::connect future was dropped while connecting to: {}.", self.ws.url() );
+					if !self.quiet
+					{
+						log::warn!( "WsMeta::connect future was dropped while connecting to: {}.", self.ws.url() );
+					}
 				}
 			}
 
-			Guard { ws: &ws }
+			Guard { ws: &ws, quiet: config.cancel_quietly }
 		};
 
 		// Listen to the events to figure out whether the connection opens successfully. We don't want to deal with
 		// the error event. Either a close event happens, in which case we want to recover the CloseEvent to return it
 		// to the user, or an Open event happens in which case we are happy campers.
 		//
-		let mut evts = pharos.observe( Self::OPEN_CLOSE.into() ).await
+		let mut evts = notifier.observe( notifier.options( Self::OPEN_CLOSE ) )
 
 			.expect( "we didn't close pharos" )
 		;
 
+		let evt = match config.connect_timeout
+		{
+			None => evts.next().await,
+
+			Some( timeout ) => match future::select( evts.next(), Delay::new( timeout ) ).await
+			{
+				Either::Left ( (evt, _) ) => evt,
+
+				// We clean up ourselves, so the guard doesn't warn about the future being dropped.
+				//
+				Either::Right( _ ) =>
+				{
+					std::mem::forget( guard );
+					abort_connect( &ws );
+
+					return Err( WsErr::ConnectTimeout );
+				}
+			}
+		};
+
 		// If the connection is closed, return error
 		//
-		if let Some( WsEvent::Closed(evt) ) = evts.next().await
+		if let Some( WsEvent::Closed(evt) ) = evt
 		{
+			std::mem::forget( guard );
+			abort_connect( &ws );
+
 			return Err( WsErr::ConnectionFailed{ event: evt } )
 		}
 
+		// The server is free to not select any protocol.
+		//
+		if config.protocol_required && ws.protocol().is_empty()
+		{
+			std::mem::forget( guard );
+			abort_connect( &ws );
+
+			return Err( WsErr::ProtocolNotNegotiated );
+		}
+
 		// We have now passed all the `await` points in this function and so the `WsStream` construction is guaranteed
 		// so we let it take over the responsibility of unregistering the callbacks by disabling our guard.
 		//
 		std::mem::forget(guard);
 
-		// We don't handle Blob's
-		//
-		ws.set_binary_type( BinaryType::Arraybuffer );
+		ws.set_binary_type( config.binary_type );
 
 
 		Ok
 		((
 			Self
 			{
-				pharos,
-				ws: ws.clone(),
+				notifier     : notifier.clone()     ,
+				ws           : ws.clone()           ,
+				close_timeout: config.close_timeout ,
+				local_close  : local_close.clone()  ,
 			},
 
 			WsStream::new
 			(
 				ws,
-				ph4,
-				SendWrapper::new( on_open  ),
-				SendWrapper::new( on_error ),
-				SendWrapper::new( on_close ),
+				notifier,
+				on_open,
+				on_error,
+				on_close,
+				local_close,
+				&config,
 			)
 		))
 	}
 
 
 
+	/// Take over a `WebSocket` that was created elsewhere, eg. by a JavaScript library. The socket can be connecting
+	/// or open. When it is already open, the [WsStream] and observers added right away, before awaiting anything
+	/// else, will still receive a [WsEvent::Open] event. When the socket is closing or closed,
+	/// [WsErr::ConnectionNotOpen] is returned.
+	///
+	/// This sets the binary type of the socket to `Arraybuffer` and replaces any event handlers set through the
+	/// `on*` properties of the socket. Listeners added with `addEventListener` are not affected. Use
+	/// [WsStream::into_inner] to hand the socket back.
+	///
+	/// Whilst the socket is connecting, the `Sink` impl of [WsStream] waits for it to open. If the connection
+	/// fails, observers receive the close event and the `Stream` ends.
+	//
+	pub fn from_websocket( ws: WebSocket ) -> Result< (Self, WsStream), WsErr >
+	{
+		let config = WsConfig::new( ws.url() );
+
+		Self::from_websocket_config( ws, config )
+	}
+
+
+
+	/// Like [WsMeta::from_websocket], but with the options of `config` that apply to an existing socket, eg.
+	/// [WsConfig::outbox] to accept messages whilst the socket is connecting. The url, protocols and connect
+	/// timeout of `config` are ignored. The binary type of the socket is set to the one of `config`.
+	//
+	pub fn from_websocket_config( ws: WebSocket, config: WsConfig ) -> Result< (Self, WsStream), WsErr >
+	{
+		config.validate()?;
+
+		let ws    = SendWrapper::new( Rc::new( ws ) );
+		let state = ws.ready_state();
+
+		if state != WebSocket::CONNECTING && state != WebSocket::OPEN
+		{
+			return Err( WsErr::ConnectionNotOpen );
+		}
+
+		let notifier    = Notifier::new( config.observer_capacity, config.observer_channel );
+		let local_close = SendWrapper::new( Rc::new( Cell::new( None ) ) );
+		let (on_open, on_error, on_close) = install_callbacks( &ws, &notifier, &local_close );
+
+		ws.set_binary_type( config.binary_type );
+
+		// The browser emitted the open event before we were listening.
+		//
+		if state == WebSocket::OPEN
+		{
+			notifier.replay_open();
+		}
+
+		Ok
+		((
+			Self
+			{
+				notifier     : notifier.clone()     ,
+				ws           : ws.clone()           ,
+				close_timeout: config.close_timeout ,
+				local_close  : local_close.clone()  ,
+			},
+
+			WsStream::new( ws, notifier, on_open, on_error, on_close, local_close, &config )
+		))
+	}
+
+
+
 	/// Close the socket. The future will resolve once the socket's state has become `WsState::CLOSED`.
 	/// See: [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close)
 	//
@@ -234,28 +353,17 @@ impl WsMeta
 				// See [MDN](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close#Exceptions_thrown).
 				//
 				self.ws.close().unwrap_throw();
+				self.local_close.set( Some( CloseInitiator::Local ) );
 
 
 				// Notify Observers
 				//
-				notify( self.pharos.clone(), WsEvent::Closing )
+				self.notifier.notify( WsEvent::Closing )
 			}
 		}
 
 
-		let mut evts = match self.pharos.observe_shared( Filter::Pointer( WsEvent::is_closed ).into() ).await
-		{
-			Ok(events) => events                    ,
-			Err(e)     => unreachable!( "{:?}", e ) , // only happens if we closed it.
-		};
-
-		// We promised the user a CloseEvent, so we don't have much choice but to unwrap this. In any case, the stream will
-		// never end and this will hang if the browser fails to send a close event.
-		//
-		let ce = evts.next().await.expect_throw( "receive a close event" );
-
-		if let WsEvent::Closed(e) = ce { Ok( e )        }
-		else                          { unreachable!() }
+		Ok( self.wait_closed().await )
 	}
 
 
@@ -263,9 +371,13 @@ impl WsMeta
 
 	/// Close the socket. The future will resolve once the socket's state has become `WsState::CLOSED`.
 	/// See: [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close)
+	///
+	/// Returns [WsErr::InvalidCloseCode] if browsers don't allow sending `code`. See [CloseCode::is_allowed].
 	//
-	pub async fn close_code( &self, code: u16  ) -> Result<CloseEvent, WsErr>
+	pub async fn close_code( &self, code: impl Into<CloseCode> ) -> Result<CloseEvent, WsErr>
 	{
+		let code = code.into().validate()?;
+
 		match self.ready_state()
 		{
 			WsState::Closed  => return Err( WsErr::ConnectionNotOpen ),
@@ -273,41 +385,40 @@ impl WsMeta
 
 			_ =>
 			{
-				match self.ws.close_with_code( code )
+				match self.ws.close_with_code( code.into() )
 				{
 					// Notify Observers
 					//
-					Ok(_) => notify( self.pharos.clone(), WsEvent::Closing ),
+					Ok(_) =>
+					{
+						self.local_close.set( Some( CloseInitiator::Local ) );
+						self.notifier.notify( WsEvent::Closing );
+					}
 
 
 					Err(_) =>
 					{
-						return Err( WsErr::InvalidCloseCode{ supplied: code } );
+						return Err( WsErr::InvalidCloseCode{ supplied: code.into() } );
 					}
 				}
 			}
 		}
 
 
-		let mut evts = match self.pharos.observe_shared( Filter::Pointer( WsEvent::is_closed ).into() ).await
-		{
-			Ok(events) => events                    ,
-			Err(e)     => unreachable!( "{:?}", e ) , // only happens if we closed it.
-		};
-
-		let ce = evts.next().await.expect_throw( "receive a close event" );
-
-		if let WsEvent::Closed(e) = ce { Ok(e)          }
-		else                          { unreachable!() }
+		Ok( self.wait_closed().await )
 	}
 
 
 
 	/// Close the socket. The future will resolve once the socket's state has become `WsState::CLOSED`.
 	/// See: [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close)
+	///
+	/// Returns [WsErr::InvalidCloseCode] if browsers don't allow sending `code`. See [CloseCode::is_allowed].
 	//
-	pub async fn close_reason( &self, code: u16, reason: impl AsRef<str>  ) -> Result<CloseEvent, WsErr>
+	pub async fn close_reason( &self, code: impl Into<CloseCode>, reason: impl AsRef<str>  ) -> Result<CloseEvent, WsErr>
 	{
+		let code = code.into().validate()?;
+
 		match self.ready_state()
 		{
 			WsState::Closed  => return Err( WsErr::ConnectionNotOpen ),
@@ -321,31 +432,56 @@ impl WsMeta
 				}
 
 
-				match self.ws.close_with_code_and_reason( code, reason.as_ref() )
+				match self.ws.close_with_code_and_reason( code.into(), reason.as_ref() )
 				{
 					// Notify Observers
 					//
-					Ok(_) => notify( self.pharos.clone(), WsEvent::Closing ),
+					Ok(_) =>
+					{
+						self.local_close.set( Some( CloseInitiator::Local ) );
+						self.notifier.notify( WsEvent::Closing );
+					}
 
 
 					Err(_) =>
 					{
-						return Err( WsErr::InvalidCloseCode{ supplied: code } )
+						return Err( WsErr::InvalidCloseCode{ supplied: code.into() } )
 					}
 				}
 			}
 		}
 
-		let mut evts = match self.pharos.observe_shared( Filter::Pointer( WsEvent::is_closed ).into() ).await
+		Ok( self.wait_closed().await )
+	}
+
+
+
+	// Wait for the close event. If the browser doesn't deliver it within the close timeout, synthesize one.
+	//
+	async fn wait_closed( &self ) -> CloseEvent
+	{
+		let mut evts = match self.notifier.observe( self.notifier.options( Filter::Pointer( WsEvent::is_closed ) ) )
 		{
 			Ok(events) => events                    ,
 			Err(e)     => unreachable!( "{:?}", e ) , // only happens if we closed it.
 		};
 
-		let ce = evts.next().await.expect_throw( "receive a close event" );
+		// We promised the user a CloseEvent, so we don't have much choice but to unwrap this. Without a close
+		// timeout, the stream will never end and this will hang if the browser fails to send a close event.
+		//
+		let ce = match self.close_timeout
+		{
+			None => evts.next().await,
+
+			Some( timeout ) => match future::select( evts.next(), Delay::new( timeout ) ).await
+			{
+				Either::Left ( (evt, _) ) => evt,
+				Either::Right( _        ) => return force_closed( &self.ws, &self.notifier, CloseInitiator::Local ),
+			}
+		};
 
-		if let WsEvent::Closed(e) = ce { Ok(e)          }
-		else                           { unreachable!() }
+		if let Some( WsEvent::Closed(e) ) = ce { e              }
+		else                                  { unreachable!() }
 	}
 
 
@@ -362,6 +498,54 @@ impl WsMeta
 	}
 
 
+	/// A `Stream` that yields the current [WsState] of the connection right away, followed by every transition.
+	/// Unlike observing the events, there is no gap between reading the state and receiving later changes.
+	///
+	/// The stream ends after [WsState::Closed]. Transitions are reported through the events, so after the
+	/// [WsStream] is dropped, which unregisters our callbacks, [WsState::Closed] will not be reported.
+	//
+	pub fn state_watch( &self ) -> WsStateWatch
+	{
+		let filter = Filter::Pointer( |evt: &WsEvent| evt.is_open() || evt.is_closing() || evt.is_closed() );
+
+		// Observing doesn't wait, so no event can happen between reading the state and observing.
+		//
+		let events = self.notifier.observe( self.notifier.options( filter ) ).expect_throw( "we didn't close pharos" );
+
+		WsStateWatch::new( self.ready_state(), events )
+	}
+
+
+	/// Resolves with the [CloseEvent] of the connection once it has closed. When it already closed before this
+	/// is called, it resolves immediately with the same event.
+	///
+	/// This does not close the connection, see [WsMeta::close] for that. As with [WsMeta::state_watch], if the
+	/// [WsStream] is dropped before the connection closed, the close event is never observed and this will not
+	/// resolve.
+	//
+	pub async fn closed( &self ) -> CloseEvent
+	{
+		if let Some( evt ) = self.notifier.closed()
+		{
+			return evt;
+		}
+
+		let mut evts = self.notifier.observe( self.notifier.options( Filter::Pointer( WsEvent::is_closed ) ) )
+
+			.expect_throw( "we didn't close pharos" )
+		;
+
+		match evts.next().await
+		{
+			Some( WsEvent::Closed( evt ) ) => evt,
+
+			// We keep the notifier alive, so the stream doesn't end.
+			//
+			_ => unreachable!(),
+		}
+	}
+
+
 	/// Access the wrapped [web_sys::WebSocket](https://docs.rs/web-sys/0.3.25/web_sys/struct.WebSocket.html) directly.
 	///
 	/// _ws_stream_wasm_ tries to expose all useful functionality through an idiomatic rust API, so hopefully
@@ -410,6 +594,18 @@ impl WsMeta
 	}
 
 
+	/// The sub-protocol the server selected as a `P`. Returns `None` when the server didn't select any or when
+	/// `P` doesn't know its name. See [WsConfig::subprotocols].
+	//
+	pub fn subprotocol<P: SubProtocol>( &self ) -> Option<P>
+	{
+		let name = self.protocol();
+
+		if name.is_empty() { None                   }
+		else               { P::from_name( &name ) }
+	}
+
+
 	/// Retrieve the address to which this socket is connected.
 	//
 	pub fn url( &self ) -> String
@@ -420,6 +616,188 @@ impl WsMeta
 
 
 
+type Callbacks =
+(
+	SendWrapper< Closure< dyn FnMut()             > > ,
+	SendWrapper< Closure< dyn FnMut()             > > ,
+	SendWrapper< Closure< dyn FnMut( JsCloseEvt ) > > ,
+);
+
+
+/// Create the callbacks that forward the open, error and close events of `ws` to `notifier` and set them on `ws`.
+/// The callbacks must be kept alive as long as they are set.
+//
+fn install_callbacks( ws: &WebSocket, notifier: &Notifier, local_close: &Rc<Cell< Option<CloseInitiator> >> ) -> Callbacks
+{
+	let no1   = notifier.clone();
+	let no2   = notifier.clone();
+	let no3   = notifier.clone();
+	let local = local_close.clone();
+
+
+	// Setup our event listeners
+	//
+	#[ allow( trivial_casts ) ]
+	//
+	let on_open = Closure::wrap( Box::new( move ||
+	{
+		// notify observers
+		//
+		no1.notify( WsEvent::Open )
+
+
+	}) as Box< dyn FnMut() > );
+
+
+	// TODO: is there no information at all in an error?
+	//
+	#[ allow( trivial_casts ) ]
+	//
+	let on_error = Closure::wrap( Box::new( move ||
+	{
+		// notify observers.
+		//
+		no2.notify( WsEvent::Error )
+
+	}) as Box< dyn FnMut() > );
+
+
+	#[ allow( trivial_casts ) ]
+	//
+	let on_close = Closure::wrap( Box::new( move |evt: JsCloseEvt|
+	{
+		let c = WsEvent::Closed( CloseEvent::from_js( &evt, local.get() ) );
+
+		no3.notify( c )
+
+	}) as Box< dyn FnMut( JsCloseEvt ) > );
+
+
+	ws.set_onopen ( Some( on_open .as_ref().unchecked_ref() ));
+	ws.set_onclose( Some( on_close.as_ref().unchecked_ref() ));
+	ws.set_onerror( Some( on_error.as_ref().unchecked_ref() ));
+
+	( SendWrapper::new( on_open ), SendWrapper::new( on_error ), SendWrapper::new( on_close ) )
+}
+
+
+
+/// The browser didn't deliver a close event in time. Stop listening for it and notify observers with a synthesized
+/// close event instead, unless that already happened. This only happens after we closed the connection ourselves,
+/// `initiator` tells why.
+//
+pub(crate) fn force_closed( ws: &WebSocket, notifier: &Notifier, initiator: CloseInitiator ) -> CloseEvent
+{
+	let evt = CloseEvent
+	{
+		code     : CloseCode::Abnormal.into() ,
+		reason   : String::new()              ,
+		was_clean: false                      ,
+		initiator                             ,
+	};
+
+	if ws.onclose().is_some()
+	{
+		log::warn!( "The close handshake didn't complete in time for: {}.", ws.url() );
+
+		ws.set_onclose( None );
+
+		notifier.notify( WsEvent::Closed( evt.clone() ) );
+	}
+
+	evt
+}
+
+
+
+/// Resolve a relative url against the location of the current document or worker and turn `http(s)` into
+/// `ws(s)`, so the browser accepts it.
+//
+fn resolve_url( url: &str ) -> Result<String, WsErr>
+{
+	let invalid = |reason| WsErr::InvalidUrl{ supplied: url.to_string(), reason };
+
+	// Both Window and WorkerGlobalScope have a location. Without one, only absolute urls can be used.
+	//
+	let base = Reflect::get( &js_sys::global(), &"location".into() ).ok()
+
+		.filter( |location| location.is_object() )
+		.and_then( |location| Reflect::get( &location, &"href".into() ).ok() )
+		.and_then( |href| href.as_string() )
+	;
+
+	let parsed = match &base
+	{
+		Some( base ) => Url::new_with_base( url, base ),
+		None         => Url::new( url )               ,
+	}
+		.map_err( |_| invalid( UrlError::Unparsable ) )?
+	;
+
+	match parsed.protocol().as_str()
+	{
+		"ws:" | "wss:" => {}
+		"http:"        => parsed.set_protocol( "ws:"  ),
+		"https:"       => parsed.set_protocol( "wss:" ),
+
+		scheme => return Err( invalid( UrlError::UnsupportedScheme{ scheme: scheme.trim_end_matches( ':' ).to_string() } ) ),
+	}
+
+	if parsed.host().is_empty()
+	{
+		return Err( invalid( UrlError::MissingHost ) );
+	}
+
+	// hash is empty for a url ending in '#', which still has an empty fragment.
+	//
+	if parsed.href().contains( '#' )
+	{
+		return Err( invalid( UrlError::Fragment ) );
+	}
+
+	Ok( parsed.href() )
+}
+
+
+
+/// Map an exception thrown by the WebSocket constructor to an error.
+//
+fn constructor_error( e: JsValue, url: &str ) -> WsErr
+{
+	let (name, message) = match e.dyn_ref::<DomException>()
+	{
+		Some( de ) => ( de.name(), de.message() ),
+
+		// The constructor only throws DOMExceptions according to the standard, but better not to crash if a
+		// browser doesn't follow it.
+		//
+		None => ( "Unknown".to_string(), format!( "{:?}", e ) ),
+	};
+
+	match name.as_str()
+	{
+		// Protocols have been validated, so this is about the url.
+		//
+		"SyntaxError"   => WsErr::InvalidUrl   { supplied: url.to_string(), reason: UrlError::Unparsable },
+		"SecurityError" => WsErr::SecurityError{ message                                                 },
+		_               => WsErr::Dom          { name, message                                           },
+	}
+}
+
+
+
+/// Unregister our callbacks and close a connection that is still being established.
+//
+fn abort_connect( ws: &WebSocket )
+{
+	ws.set_onopen ( None );
+	ws.set_onclose( None );
+	ws.set_onerror( None );
+	ws.close().unwrap_throw(); // cannot throw without code and reason.
+}
+
+
+
 impl fmt::Debug for WsMeta
 {
 	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
@@ -436,8 +814,60 @@ impl Observable<WsEvent> for WsMeta
 
 	fn observe( &mut self, options: ObserveConfig<WsEvent> ) -> Observe< '_, WsEvent, Self::Error >
 	{
-		self.pharos.observe( options )
+		future::ready( self.notifier.observe( options ) ).boxed()
 	}
 }
 
 
+
+
+
+#[ cfg( test ) ]
+//
+mod tests
+{
+	// What's tested:
+	//
+	// ✔ Verify exceptions of the WebSocket constructor are mapped to the matching error
+	//
+	use { super::*, wasm_bindgen_test::* };
+
+	wasm_bindgen_test_configure!(run_in_browser);
+
+
+	fn exception( name: &str ) -> JsValue
+	{
+		DomException::new_with_message_and_name( "thrown by the test", name )
+
+			.expect_throw( "create DomException" )
+			.into()
+	}
+
+
+	// Verify exceptions of the WebSocket constructor are mapped to the matching error.
+	//
+	#[ wasm_bindgen_test ]
+	//
+	fn constructor_errors()
+	{
+		let url = "ws://127.0.0.1:3212/";
+
+		assert_eq!
+		(
+			WsErr::InvalidUrl{ supplied: url.to_string(), reason: UrlError::Unparsable },
+			constructor_error( exception( "SyntaxError" ), url ),
+		);
+
+		assert_eq!
+		(
+			WsErr::SecurityError{ message: "thrown by the test".to_string() },
+			constructor_error( exception( "SecurityError" ), url ),
+		);
+
+		assert_eq!
+		(
+			WsErr::Dom{ name: "NotSupportedError".to_string(), message: "thrown by the test".to_string() },
+			constructor_error( exception( "NotSupportedError" ), url ),
+		);
+	}
+}
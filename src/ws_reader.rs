@@ -0,0 +1,337 @@
+use crate::{ import::*, ws_stream::Core, * };
+
+
+// What the browser reported, in order.
+//
+pub(crate) enum Incoming
+{
+	Message( JsMessage ),
+	Event  ( WsEvent   ),
+}
+
+
+// Received messages and events in order. Only messages count towards the receive capacity.
+//
+#[ derive( Default ) ]
+//
+pub(crate) struct Inbox
+{
+	items   : VecDeque<Incoming>,
+	messages: usize             ,
+}
+
+
+impl Inbox
+{
+	pub(crate) fn messages( &self ) -> usize
+	{
+		self.messages
+	}
+
+
+	pub(crate) fn push_message( &mut self, msg: JsMessage )
+	{
+		self.messages += 1;
+		self.items.push_back( Incoming::Message( msg ) );
+	}
+
+
+	// Events don't count toward the receive capacity, so the ones that repeat while nobody reads are coalesced:
+	// between two messages there is at most one ReceiveQueueFull error and one Pong, which has the latest round
+	// trip time.
+	//
+	pub(crate) fn push_event( &mut self, evt: WsEvent )
+	{
+		let since_message = self.items.iter_mut().rev()
+
+			.take_while( |item| !matches!( item, Incoming::Message(_) ) )
+		;
+
+		for item in since_message
+		{
+			match ( item, &evt )
+			{
+				( Incoming::Event( WsEvent::WsErr( WsErr::ReceiveQueueFull ) ), WsEvent::WsErr( WsErr::ReceiveQueueFull ) ) => return,
+
+				( Incoming::Event( WsEvent::Pong{ rtt } ), WsEvent::Pong{ rtt: latest } ) =>
+				{
+					*rtt = *latest;
+					return;
+				}
+
+				_ => {}
+			}
+		}
+
+		self.items.push_back( Incoming::Event( evt ) );
+	}
+
+
+	pub(crate) fn drop_oldest_message( &mut self )
+	{
+		if let Some( pos ) = self.items.iter().position( |item| matches!( item, Incoming::Message(_) ) )
+		{
+			self.items.remove( pos );
+			self.messages -= 1;
+		}
+	}
+
+
+	fn pop( &mut self ) -> Option<Incoming>
+	{
+		let item = self.items.pop_front();
+
+		if let Some( Incoming::Message(_) ) = item
+		{
+			self.messages -= 1;
+		}
+
+		item
+	}
+}
+
+
+// The inbox, shared with the message callback and the notifier.
+//
+pub(crate) type Queue = SendWrapper< Rc<RefCell< Inbox >> >;
+
+
+/// The receiving half of a [WsStream], obtained with [WsStream::split]. A `Stream` of [WsMessage].
+///
+/// The connection is only closed when both halves are dropped, or when the [WsWriter] is closed. Dropping the reader
+/// alone doesn't close the connection, but incoming messages will be discarded from then on.
+//
+pub struct WsReader
+{
+	core: SendWrapper< Rc<Core> >,
+
+	// The received messages and events. Binary data is only copied into WASM memory when it is consumed.
+	//
+	queue: Queue,
+
+	// Last waker of task that wants to read incoming messages to be woken up on a new message
+	//
+	waker: SendWrapper< Rc<RefCell< Option<Waker> >> >,
+
+	// A Blob message taken from the queue which is being read.
+	//
+	blob_read: Option<SendWrapper< JsFuture >>,
+
+	// Whether the close event has been taken from the queue.
+	//
+	closed: bool,
+}
+
+
+
+impl WsReader
+{
+	pub(crate) fn new
+	(
+		core : SendWrapper< Rc<Core> >                     ,
+		queue: Queue                                       ,
+		waker: SendWrapper< Rc<RefCell< Option<Waker> >> > ,
+	)
+		-> Self
+	{
+		Self { core, queue, waker, blob_read: None, closed: false }
+	}
+
+
+	pub(crate) fn core( &self ) -> &Rc<Core>
+	{
+		&self.core
+	}
+
+
+	/// The number of received messages that are buffered, waiting to be consumed through the `Stream` impl.
+	/// This allows monitoring whether the consumer keeps up. See [WsConfig::receive_capacity](crate::WsConfig::receive_capacity)
+	/// to bound the buffer.
+	//
+	pub fn queue_len( &self ) -> usize
+	{
+		self.queue.borrow().messages()
+	}
+
+
+	// Whether the connection is closed and no message is left to yield.
+	//
+	pub(crate) fn is_drained( &self ) -> bool
+	{
+		self.ready_state() == WsState::Closed && self.queue_len() == 0 && self.blob_read.is_none()
+	}
+
+
+	/// Verify the [WsState] of the connection.
+	//
+	pub fn ready_state( &self ) -> WsState
+	{
+		self.core.ready_state()
+	}
+
+
+	/// A `Stream` over the incoming messages that doesn't copy binary data into WASM memory. See [JsMessage].
+	/// Messages are taken from the same queue as the `Stream` impl.
+	//
+	pub fn js_messages( &mut self ) -> JsMessages<'_>
+	{
+		JsMessages::new( self )
+	}
+
+
+	/// A `Stream` over the incoming messages that yields decoding errors in-band and ends with
+	/// [WsErr::ConnectionLost] when the connection is lost. See [TryMessages]. Messages are taken from the
+	/// same queue as the `Stream` impl.
+	//
+	pub fn try_messages( &mut self ) -> TryMessages<'_>
+	{
+		TryMessages::new( self )
+	}
+
+
+	/// A `Stream` over the incoming messages and the events of the connection, in the order the browser reported
+	/// them. See [WsItems]. Messages are taken from the same queue as the `Stream` impl. Repeated [WsEvent::Pong]
+	/// and [WsErr::ReceiveQueueFull] events between two messages are coalesced into one.
+	//
+	pub fn items( &mut self ) -> WsItems<'_>
+	{
+		WsItems::new( self )
+	}
+
+
+	/// Put the halves of a [WsStream] back together. Fails if `writer` doesn't belong to the same
+	/// [WsStream] as `self`.
+	//
+	// The error hands back both halves, so it can't be small.
+	//
+	#[ allow( clippy::result_large_err ) ]
+	//
+	pub fn reunite( self, writer: WsWriter ) -> Result< WsStream, ReuniteError >
+	{
+		if Rc::ptr_eq( self.core(), writer.core() )
+		{
+			Ok( WsStream::from_halves( self, writer ) )
+		}
+
+		else
+		{
+			Err( ReuniteError( self, writer ) )
+		}
+	}
+
+
+	// When `read_blobs` is set, Blob messages are read before being returned as `JsMessage::Binary`. Messages
+	// behind a blob wait for it to be read, so the order is preserved.
+	//
+	// Without `until_closed`, this ends as soon as the connection is closing and the queue is empty. With it, this
+	// ends after the close event.
+	//
+	pub(crate) fn poll_next_incoming( &mut self, cx: &mut Context<'_>, read_blobs: bool, until_closed: bool ) -> Poll<Option< Incoming >>
+	{
+		loop
+		{
+			if let Some( read ) = &mut self.blob_read
+			{
+				let res = ready!( Pin::new( &mut **read ).poll( cx ) );
+
+				self.blob_read = None;
+
+				match res
+				{
+					Ok ( buf ) => return Some( Incoming::Message( JsMessage::Binary( JsBytes::new( Uint8Array::new( &buf ) ) ) ) ).into(),
+
+					// The message is lost, but the next ones might be fine. The error takes the place of the message.
+					//
+					Err( _ ) =>
+					{
+						let evt = WsEvent::WsErr( WsErr::CantDecodeBlob );
+
+						self.core.notifier().notify_observers( evt.clone() );
+						return Some( Incoming::Event( evt ) ).into();
+					}
+				}
+			}
+
+			// Once the queue is empty, check the state of the connection.
+			// When it is closing or closed, no more messages will arrive, so
+			// return Poll::Ready( None )
+			//
+			let item = match self.queue.borrow_mut().pop()
+			{
+				Some( item ) => item,
+
+				None =>
+				{
+					*self.waker.borrow_mut() = Some( cx.waker().clone() );
+
+					return match self.ready_state()
+					{
+						WsState::Open | WsState::Connecting => Poll::Pending ,
+
+						// The close event will be put in the queue.
+						//
+						_ if until_closed && !self.closed => Poll::Pending,
+
+						_ => None.into(),
+					}
+				}
+			};
+
+			// As long as there is things in the queue, just keep reading
+			//
+			match item
+			{
+				Incoming::Message( JsMessage::Blob( blob ) ) if read_blobs =>
+				{
+					self.blob_read = Some( SendWrapper::new( JsFuture::from( blob.as_blob().array_buffer() ) ) );
+				}
+
+				item =>
+				{
+					if let Incoming::Event( WsEvent::Closed(_) ) = item
+					{
+						self.closed = true;
+					}
+
+					return Some( item ).into();
+				}
+			}
+		}
+	}
+}
+
+
+
+impl fmt::Debug for WsReader
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "WsReader for connection: {}", self.core.ws().url() )
+	}
+}
+
+
+
+impl Stream for WsReader
+{
+	type Item = WsMessage;
+
+	// Currently requires an unfortunate copy from Js memory to WASM memory. Hopefully one
+	// day we will be able to receive the MessageEvt directly in WASM. If you don't need the
+	// data in WASM memory, use `js_messages`.
+	//
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		loop
+		{
+			// Events are skipped, they have been reported to observers.
+			//
+			match ready!( self.poll_next_incoming( cx, true, false ) )
+			{
+				Some( Incoming::Message( msg ) ) => return Some( WsMessage::try_from( msg ).expect_throw( "blobs have been read" ) ).into(),
+				Some( Incoming::Event  ( _   ) ) => continue,
+				None                             => return None.into(),
+			}
+		}
+	}
+}
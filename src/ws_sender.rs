@@ -0,0 +1,141 @@
+use crate::{ import::*, delay::Delay, ws_stream::Core, * };
+
+
+/// A cheap, cloneable handle to send messages on a connection, obtained with [WsStream::sender] or
+/// [WsWriter::sender]. Sending doesn't require exclusive access to the stream, so several parts of an
+/// application can send on the same connection without funneling everything through a channel.
+///
+/// A sender doesn't keep the connection alive. Once the [WsStream], or both its halves, have been dropped,
+/// sending returns [WsErr::ConnectionNotOpen].
+///
+/// Messages from different senders are handed to the browser in the order the sends complete.
+//
+#[ derive( Clone ) ]
+//
+pub struct WsSender
+{
+	core          : SendWrapper< Weak<Core> > ,
+	watermarks    : Option<(u32, u32)>        ,
+	drain_interval: Duration                  ,
+}
+
+
+
+impl WsSender
+{
+	pub(crate) fn new( core: &Rc<Core>, watermarks: Option<(u32, u32)>, drain_interval: Duration ) -> Self
+	{
+		Self
+		{
+			core: SendWrapper::new( Rc::downgrade( core ) ),
+			watermarks                                     ,
+			drain_interval                                 ,
+		}
+	}
+
+
+	/// Verify the [WsState] of the connection. Reports [WsState::Closed] once the stream has been dropped.
+	//
+	pub fn ready_state( &self ) -> WsState
+	{
+		self.core.upgrade().map( |core| core.ready_state() ).unwrap_or( WsState::Closed )
+	}
+
+
+	/// Send a message right away. This has the same semantics as `Sink::start_send` on [WsStream]:
+	/// when the connection is not open, [WsErr::ConnectionNotOpen] is returned. Backpressure configured
+	/// with [WsConfig::send_watermarks] is not taken into account.
+	//
+	pub fn try_send( &self, msg: WsMessage ) -> Result<(), WsErr>
+	{
+		match self.core.upgrade()
+		{
+			Some( core ) => core.send( msg )               ,
+			None         => Err( WsErr::ConnectionNotOpen ),
+		}
+	}
+
+
+	/// Send a message once the connection is ready. This has the same semantics as `SinkExt::feed` on
+	/// [WsStream]: it waits while the connection is connecting and, with [WsConfig::send_watermarks], while
+	/// `bufferedAmount` is above the high watermark, until it drops below the low watermark. When the connection
+	/// is closing or closed, [WsErr::ConnectionNotOpen] is returned.
+	//
+	pub async fn send( &self, msg: WsMessage ) -> Result<(), WsErr>
+	{
+		self.ready().await?;
+		self.try_send( msg )
+	}
+
+
+	async fn ready( &self ) -> Result<(), WsErr>
+	{
+		let mut draining = false;
+
+		loop
+		{
+			// Don't hold on to the core across await points, so the connection can still close when the
+			// stream is dropped.
+			//
+			let ( state, buffered, room, notifier ) = match self.core.upgrade()
+			{
+				Some( core ) => ( core.ready_state(), core.ws().buffered_amount(), core.outbox_has_room(), core.notifier().clone() ),
+				None         => return Err( WsErr::ConnectionNotOpen ),
+			};
+
+			match state
+			{
+				WsState::Connecting if room => return Ok(()),
+
+				WsState::Connecting =>
+				{
+					let filter = Filter::Pointer( |e: &WsEvent| e.is_open() || e.is_closing() || e.is_closed() );
+
+					// Observing is synchronous, so the connection can't have opened since we checked the state.
+					//
+					let mut evts = notifier.observe( notifier.options( filter ) )
+
+						.map_err( |_| WsErr::ConnectionNotOpen )?
+					;
+
+					evts.next().await;
+				}
+
+				WsState::Open => match self.watermarks
+				{
+					None => return Ok(()),
+
+					Some(( high, low )) =>
+					{
+						draining = draining || buffered > high;
+
+						if !draining || buffered <= low
+						{
+							return Ok(());
+						}
+
+						// The browser has no event for this, so we poll it on a timer.
+						//
+						Delay::new( self.drain_interval ).await;
+					}
+				}
+
+				_ => return Err( WsErr::ConnectionNotOpen ),
+			}
+		}
+	}
+}
+
+
+
+impl fmt::Debug for WsSender
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		match self.core.upgrade()
+		{
+			Some( core ) => write!( f, "WsSender for connection: {}", core.ws().url() ),
+			None         => write!( f, "WsSender for a dropped connection" ),
+		}
+	}
+}
@@ -1,4 +1,4 @@
-use crate :: { import::*, WsErr };
+use crate :: { import::*, WsErr, WsEvent };
 
 
 /// Indicates the state of a Websocket connection. The only state in which it's valid to send and receive messages
@@ -40,3 +40,70 @@ impl TryFrom<u16> for WsState
 		}
 	}
 }
+
+
+
+/// A `Stream` of the [WsState] of a connection, created with [WsMeta::state_watch](crate::WsMeta::state_watch).
+///
+/// It yields the state at the time it was created, followed by every transition. The stream ends after
+/// [WsState::Closed].
+//
+#[ derive( Debug ) ]
+//
+pub struct WsStateWatch
+{
+	next  : Option<WsState> ,
+	last  : WsState         ,
+	events: Events<WsEvent> ,
+}
+
+
+
+impl WsStateWatch
+{
+	pub(crate) fn new( current: WsState, events: Events<WsEvent> ) -> Self
+	{
+		Self { next: Some( current ), last: current, events }
+	}
+}
+
+
+
+impl Stream for WsStateWatch
+{
+	type Item = WsState;
+
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		if let Some( state ) = self.next.take()
+		{
+			return Poll::Ready( Some( state ) );
+		}
+
+		loop
+		{
+			if self.last == WsState::Closed
+			{
+				return Poll::Ready( None );
+			}
+
+			let state = match ready!( Pin::new( &mut self.events ).poll_next( cx ) )
+			{
+				Some( WsEvent::Open      ) => WsState::Open    ,
+				Some( WsEvent::Closing   ) => WsState::Closing ,
+				Some( WsEvent::Closed(_) ) => WsState::Closed  ,
+				Some( _                  ) => continue         ,
+				None                       => return Poll::Ready( None ),
+			};
+
+			// The browser updates the ready state before it fires the event, so the state we started with
+			// might already reflect the first event.
+			//
+			if state != self.last
+			{
+				self.last = state;
+				return Poll::Ready( Some( state ) );
+			}
+		}
+	}
+}
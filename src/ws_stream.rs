@@ -1,4 +1,4 @@
-use crate::{ import::*, * };
+use crate::{ import::*, heartbeat::{ self, Pongs }, idle_timeout, notifier::Notifier, outbox::Outbox, ws_reader::Inbox, * };
 
 
 /// A futures 0.3 Sink/Stream of [WsMessage]. Created with [WsMeta::connect](crate::WsMeta::connect).
@@ -11,36 +11,51 @@ use crate::{ import::*, * };
 /// Since this implements [`Sink`], it has to have a close method. This method will call the
 /// web api [`WebSocket.close`](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close)
 /// without parameters. Eg. a default value of `1005` will be assumed for the close code. The
-/// situation is the same when dropping without calling close.
+/// situation is the same when dropping without calling close. A close code for both cases can be
+/// configured with [WsConfig::drop_close_code](crate::WsConfig::drop_close_code).
 ///
 /// **Warning**: This object holds the callbacks needed to receive events from the browser.
 /// If you drop it before the close event was emitted, you will no longer receive events. Thus,
 /// observers will never receive a `Close` event. Drop will issue a `Closing` event and this
 /// will be the very last event observers receive. The the stream will end if `WsMeta` is also dropped.
 ///
+/// ## Splitting
+///
+/// [WsStream::split] gives a [WsReader] and a [WsWriter] which can be used from different tasks without
+/// the locking that `StreamExt::split` requires.
+///
 /// See the [integration tests](https://github.com/najamelan/ws_stream_wasm/blob/release/tests/futures_codec.rs)
 /// if you need an example.
 ///
 //
 pub struct WsStream
 {
-	ws: SendWrapper< Rc< WebSocket > >,
+	reader: WsReader,
+	writer: WsWriter,
+}
 
-	// The queue of received messages
-	//
-	queue: SendWrapper< Rc<RefCell< VecDeque<WsMessage> >> >,
 
-	// Last waker of task that wants to read incoming messages to be woken up on a new message
-	//
-	waker: SendWrapper< Rc<RefCell< Option<Waker> >> >,
 
-	// Last waker of task that wants to write to the Sink
-	//
-	sink_waker: SendWrapper< Rc<RefCell< Option<Waker> >> >,
+/// Returned by [WsReader::reunite] when the halves don't belong to the same [WsStream]. Gives the halves back.
+//
+#[ derive( Debug, Error ) ]
+//
+#[ error( "Tried to reunite a WsReader and a WsWriter that don't belong to the same WsStream." ) ]
+//
+pub struct ReuniteError( pub WsReader, pub WsWriter );
 
-	// A pointer to the pharos of WsMeta for when we need to listen to events
+
+
+// The state shared by both halves of a WsStream. The connection is closed when it is dropped, so when both
+// halves are gone.
+//
+pub(crate) struct Core
+{
+	ws: SendWrapper< Rc<WebSocket> >,
+
+	// Delivers events to the observers of WsMeta and to the receive queue.
 	//
-	pharos: SharedPharos<WsEvent>,
+	notifier: Notifier,
 
 	// The callback closures.
 	//
@@ -49,12 +64,199 @@ pub struct WsStream
 	_on_close: SendWrapper< Closure< dyn FnMut( JsCloseEvt   ) > >,
 	_on_mesg : SendWrapper< Closure< dyn FnMut( MessageEvent ) > >,
 
-	// This allows us to store a future to poll when Sink::poll_close is called
+	// The close code to use when we close the connection ourselves.
+	//
+	drop_close_code: Option<CloseCode>,
+
+	// Set when we close the connection ourselves, shared with WsMeta and the close callback.
+	//
+	local_close: SendWrapper< Rc<Cell< Option<CloseInitiator> >> >,
+
+	// The round trip time measured by the heartbeat.
+	//
+	pongs: SendWrapper< Rc<RefCell< Pongs >> >,
+
+	// When a message was last sent or received, in milliseconds since the epoch. Used by the idle timeout.
+	//
+	last_activity: SendWrapper< Rc<Cell<f64>> >,
+
+	// Messages sent while connecting, if enabled.
+	//
+	outbox: Option<Rc< Outbox >>,
+
+	// Stops the background tasks: waking the writer and settling the outbox, the heartbeat and the idle timeout.
+	//
+	tasks: Vec<AbortHandle>,
+
+	// Set by `into_inner`, so drop doesn't close the connection.
+	//
+	released: Cell<bool>,
+}
+
+
+
+impl Core
+{
+	pub(crate) fn ws( &self ) -> &Rc<WebSocket>
+	{
+		&self.ws
+	}
+
+
+	pub(crate) fn notifier( &self ) -> &Notifier
+	{
+		&self.notifier
+	}
+
+
+	pub(crate) fn ready_state( &self ) -> WsState
+	{
+		self.ws.ready_state().try_into()
+
+			// This can't throw unless the browser gives us an invalid ready state
+			//
+			.expect_throw( "Convert ready state from browser API" )
+	}
+
+
+	// Whether a message sent now would be accepted without waiting for the connection to open.
+	//
+	pub(crate) fn outbox_has_room( &self ) -> bool
+	{
+		self.outbox.as_ref().map( |outbox| outbox.has_room() ).unwrap_or( false )
+	}
+
+
+	// Send a message if the connection is open, or queue it in the outbox if it is connecting.
+	//
+	pub(crate) fn send( &self, item: WsMessage ) -> Result<(), WsErr>
+	{
+		match ( self.ready_state(), &self.outbox )
+		{
+			( WsState::Open, Some( outbox ) ) => outbox.flush( &self.ws, &self.last_activity ),
+			( WsState::Open, None           ) => {}
+
+			( WsState::Connecting, Some( outbox ) ) => return outbox.push( item ),
+
+			// Connecting, Closing or Closed
+			//
+			_ => return Err( WsErr::ConnectionNotOpen ),
+		}
+
+		// The send method can return 2 errors:
+		// - unpaired surrogates in UTF (we shouldn't get those in rust strings)
+		// - connection is already closed.
+		//
+		// So if this returns an error, we will return ConnectionNotOpen. In principle
+		// we just checked that it's open, but this guarantees correctness.
+		//
+		match item
+		{
+			WsMessage::Binary( d ) => self.ws.send_with_u8_array( &d ).map_err( |_| WsErr::ConnectionNotOpen )? ,
+			WsMessage::Text  ( s ) => self.ws.send_with_str     ( &s ).map_err( |_| WsErr::ConnectionNotOpen )? ,
+		}
+
+		// Record that a message was sent, for the idle timeout.
+		//
+		self.last_activity.set( js_sys::Date::now() );
+
+		Ok(())
+	}
+
+
+	// Send messages that were queued elsewhere, eg. by ReconnectingWsStream while it waited for this connection.
+	// Only call this when the connection is open.
+	//
+	pub(crate) fn flush_outbox( &self, outbox: &Outbox )
+	{
+		outbox.flush( &self.ws, &self.last_activity );
+	}
+
+
+	// Send what is left in the outbox if the connection is open, otherwise apply the policy for failed connections.
+	//
+	fn settle_outbox( &self )
+	{
+		if let Some( outbox ) = &self.outbox
+		{
+			match self.ready_state()
+			{
+				WsState::Open => outbox.flush( &self.ws, &self.last_activity ),
+				_             => outbox.fail( &self.notifier ),
+			}
+		}
+	}
+
+
+	fn unregister( &self )
+	{
+		for task in &self.tasks
+		{
+			task.abort();
+		}
+
+		self.ws.set_onmessage( None );
+		self.ws.set_onerror  ( None );
+		self.ws.set_onopen   ( None );
+		self.ws.set_onclose  ( None );
+	}
+
+
+	// Close the connection with the configured close code, if any.
 	//
-	closer: Option<SendWrapper< Pin<Box< dyn Future< Output=() > + Send >> >>,
+	pub(crate) fn close_ws( &self ) -> Result< (), JsValue >
+	{
+		match self.drop_close_code
+		{
+			Some( code ) => self.ws.close_with_code( code.into() )?,
+			None         => self.ws.close()?                      ,
+		}
+
+		self.local_close.set( Some( CloseInitiator::Local ) );
+
+		Ok(())
+	}
 }
 
 
+
+impl Drop for Core
+{
+	// We don't block here, just tell the browser to close the connection and move on.
+	//
+	fn drop( &mut self )
+	{
+		if self.released.get()
+		{
+			return;
+		}
+
+		self.settle_outbox();
+
+		match self.ready_state()
+		{
+			WsState::Closing | WsState::Closed => {}
+
+			_ =>
+			{
+				// This can't fail. Only exceptions are related to invalid
+				// close codes and reason strings to long, and the close code was validated by WsConfig.
+				//
+				self.close_ws().expect( "WsStream::drop - close ws socket" );
+
+
+				// Notify Observers. This event is not emitted by the websocket API.
+				//
+				self.notifier.notify( WsEvent::Closing )
+			}
+		}
+
+		self.unregister();
+	}
+}
+
+
+
 impl WsStream
 {
 	/// Create a new WsStream.
@@ -62,10 +264,12 @@ impl WsStream
 	pub(crate) fn new
 	(
 		ws      : SendWrapper< Rc<WebSocket> > ,
-		pharos  : SharedPharos<WsEvent>        ,
+		notifier: Notifier                     ,
 		on_open : SendWrapper< Closure< dyn FnMut()               > > ,
 		on_error: SendWrapper< Closure< dyn FnMut()               > > ,
 		on_close: SendWrapper< Closure< dyn FnMut( JsCloseEvt   ) > > ,
+		local   : SendWrapper< Rc<Cell< Option<CloseInitiator> >>                       > ,
+		config  : &WsConfig                                           ,
 
 	) -> Self
 
@@ -73,10 +277,24 @@ impl WsStream
 		let waker     : SendWrapper< Rc<RefCell<Option<Waker>>> > = SendWrapper::new( Rc::new( RefCell::new( None )) );
 		let sink_waker: SendWrapper< Rc<RefCell<Option<Waker>>> > = SendWrapper::new( Rc::new( RefCell::new( None )) );
 
-		let queue = SendWrapper::new( Rc::new( RefCell::new( VecDeque::new() ) ) );
-		let q2    = queue.clone();
+		let queue = SendWrapper::new( Rc::new( RefCell::new( Inbox::default() ) ) );
+		let q2    = Rc::downgrade( &queue );
 		let w2    = waker.clone();
-		let ph2   = pharos.clone();
+		let no2   = notifier.clone();
+		let ws2   = ws.clone();
+		let lc2   = local.clone();
+		let pongs = SendWrapper::new( Rc::new( RefCell::new( Pongs::default() ) ) );
+		let po2   = pongs.clone();
+		let hb2   = config.heartbeat.clone();
+		let last  = SendWrapper::new( Rc::new( Cell::new( js_sys::Date::now() ) ) );
+		let la2   = last.clone();
+		let cap   = config.receive_capacity;
+		let pol   = config.overflow_policy;
+
+		// Whether we warned about dropping messages since the queue last had room, so a peer flooding a full
+		// queue doesn't flood the log.
+		//
+		let mut warned = false;
 
 
 		// Send the incoming ws messages to the WsMeta object
@@ -85,10 +303,95 @@ impl WsStream
 		//
 		let on_mesg = Closure::wrap( Box::new( move |msg_evt: MessageEvent|
 		{
-			match WsMessage::try_from( msg_evt )
+			let msg = match JsMessage::try_from( msg_evt )
+			{
+				// Pongs are not for the consumer.
+				//
+				Ok( msg ) if hb2.as_ref().map( |hb| heartbeat::receive( hb, &po2, &no2, &msg ) ).unwrap_or( false ) => return,
+
+				Ok( msg ) => msg,
+
+				// The notifier puts the error in the queue, in the position of the message.
+				//
+				Err( err ) =>
+				{
+					la2.set( js_sys::Date::now() );
+					no2.notify( WsEvent::WsErr( err ) );
+					return;
+				}
+			};
+
+			la2.set( js_sys::Date::now() );
+
+			let queue = match q2.upgrade()
+			{
+				Some( queue ) => queue,
+
+				// The reader has been dropped, nobody will consume the message.
+				//
+				None => return,
+			};
+
+			// The borrow of the queue must be released before notifying, as the notifier also puts the
+			// event in the queue.
+			//
+			let evt =
+			{
+				let mut queue = queue.borrow_mut();
+
+				if !cap.map( |cap| queue.messages() >= cap ).unwrap_or( false )
+				{
+					warned = false;
+					queue.push_message( msg );
+					None
+				}
+
+				else
+				{
+					match pol
+					{
+						OverflowPolicy::DropNewest =>
+						{
+							if !warned
+							{
+								log::warn!( "WsStream: receive queue is full, dropping incoming messages until it has room." );
+								warned = true;
+							}
+
+							None
+						}
+
+						OverflowPolicy::DropOldest =>
+						{
+							queue.drop_oldest_message();
+							queue.push_message( msg );
+							None
+						}
+
+						OverflowPolicy::Error => Some( WsEvent::WsErr( WsErr::ReceiveQueueFull ) ),
+
+						// Only close once, more messages might arrive while closing.
+						//
+						OverflowPolicy::Close( code ) if ws2.ready_state() == WebSocket::OPEN =>
+						{
+							log::warn!( "WsStream: receive queue is full, closing the connection." );
+
+							// The close code was validated by WsConfig.
+							//
+							ws2.close_with_code( code.into() ).expect_throw( "close ws socket" );
+							lc2.set( Some( CloseInitiator::Local ) );
+
+							Some( WsEvent::Closing )
+						}
+
+						OverflowPolicy::Close(_) => None,
+					}
+				}
+			};
+
+			if let Some( evt ) = evt
 			{
-				Ok (msg) => q2.borrow_mut().push_back( msg ),
-				Err(err) => notify( ph2.clone(), WsEvent::WsErr( err ) ),
+				no2.notify( evt );
 			}
 
 			if let Some( w ) = w2.borrow_mut().take()
@@ -102,297 +405,295 @@ impl WsStream
 		// Install callback
 		//
 		ws.set_onmessage  ( Some( on_mesg.as_ref().unchecked_ref() ) );
+		notifier.attach( &queue, &waker );
 
 
-		// When the connection closes, we need to verify if there are any tasks
-		// waiting on poll_next. We need to wake them up.
+		// A task waiting to write on a connection that is still connecting needs to be woken up when it opens
+		// or closes. Tasks waiting on poll_next are woken up by the notifier.
+		// The outbox is flushed when the connection opens, and its policy applied when it fails.
 		//
-		let ph    = pharos    .clone();
-		let wake  = waker     .clone();
+		let no3   = notifier  .clone();
 		let swake = sink_waker.clone();
+		let ws2   = ws        .clone();
+		let la3   = last      .clone();
+
+		let outbox = config.outbox.map( |(capacity, policy)| Rc::new( Outbox::new( capacity, policy ) ) );
+		let ob2    = outbox.clone();
+
+		let filter = Filter::Pointer( |evt: &WsEvent| evt.is_open() || evt.is_closed() );
+
+		let mut rx = notifier.observe( notifier.options( filter ) ).expect_throw( "we didn't close pharos" );
 
 		let wake_on_close = async move
 		{
-			let mut rx;
-
-			// Scope to avoid borrowing across await point.
-			//
+			while let Some( evt ) = rx.next().await
 			{
-				match ph.observe_shared( Filter::Pointer( WsEvent::is_closed ).into() ).await
+				if let Some( outbox ) = &ob2
 				{
-					Ok(events) => rx = events               ,
-					Err(e)     => unreachable!( "{:?}", e ) , // only happens if we closed it.
-				}
-			}
+					match &evt
+					{
+						WsEvent::Open if ws2.ready_state() == WebSocket::OPEN => outbox.flush( &ws2, &la3 ),
 
-			rx.next().await;
+						WsEvent::Closed(_) => outbox.fail( &no3 ),
+						_                  => {}
+					}
+				}
 
-			if let Some(w) = &*wake.borrow()
-			{
-				w.wake_by_ref();
-			}
+				if let Some(w) = &*swake.borrow()
+				{
+					w.wake_by_ref();
+				}
 
-			if let Some(w) = &*swake.borrow()
-			{
-				w.wake_by_ref();
+				if evt.is_closed()
+				{
+					break;
+				}
 			}
 		};
 
-		spawn_local( wake_on_close );
+		let mut tasks = Vec::new();
+
+		let (wake_on_close, abort) = future::abortable( wake_on_close );
 
+		spawn_local( wake_on_close.map( |_| () ) );
+		tasks.push( abort );
+
+		if let Some( hb ) = config.heartbeat.clone()
+		{
+			let driver = heartbeat::drive( hb, ws.clone(), (*pongs).clone(), notifier.clone(), local.clone(), config.close_timeout );
+			let (driver, abort) = future::abortable( driver );
+
+			spawn_local( driver.map( |_| () ) );
+			tasks.push( abort );
+		}
+
+		if let Some(( timeout, reason )) = config.idle_timeout.clone()
+		{
+			let driver = idle_timeout::drive( timeout, reason, ws.clone(), (*last).clone(), notifier.clone(), local.clone() );
+			let (driver, abort) = future::abortable( driver );
+
+			spawn_local( driver.map( |_| () ) );
+			tasks.push( abort );
+		}
+
+
+		let core = SendWrapper::new( Rc::new( Core
+		{
+			ws                                             ,
+			notifier                                       ,
+			drop_close_code: config.drop_close_code        ,
+			local_close    : local                         ,
+			pongs                                          ,
+			last_activity  : last                          ,
+			outbox                                         ,
+			tasks                                          ,
+			released       : Cell::new( false )            ,
+			_on_mesg       : SendWrapper::new( on_mesg )   ,
+			_on_open       : on_open                       ,
+			_on_error      : on_error                      ,
+			_on_close      : on_close                      ,
+		}));
 
 		Self
 		{
-			ws                                      ,
-			queue                                   ,
-			waker                                   ,
-			sink_waker                              ,
-			pharos                                  ,
-			closer    : None                        ,
-			_on_mesg  : SendWrapper::new( on_mesg ) ,
-			_on_open  : on_open                     ,
-			_on_error : on_error                    ,
-			_on_close : on_close                    ,
+			reader: WsReader::new( core.clone(), queue, waker ) ,
+			writer: WsWriter::new( core, sink_waker, config   ) ,
 		}
 	}
 
 
 
-	/// Verify the [WsState] of the connection.
-	//
-	pub fn ready_state( &self ) -> WsState
+	pub(crate) fn from_halves( reader: WsReader, writer: WsWriter ) -> Self
 	{
-		self.ws.ready_state().try_into()
-
-			// This can't throw unless the browser gives us an invalid ready state
-			//
-			.expect_throw( "Convert ready state from browser API" )
+		Self { reader, writer }
 	}
 
 
 
-	/// Access the wrapped [web_sys::WebSocket](https://docs.rs/web-sys/0.3.25/web_sys/struct.WebSocket.html) directly.
+	/// Split into a [WsReader] and a [WsWriter] that can be used independently. They share the underlying
+	/// connection directly, without a lock. Use [WsReader::reunite] to put them back together.
 	///
-	/// _ws_stream_wasm_ tries to expose all useful functionality through an idiomatic rust API, so hopefully
-	/// you won't need this, however if I missed something, you can.
-	///
-	/// ## Caveats
-	/// If you call `set_onopen`, `set_onerror`, `set_onmessage` or `set_onclose` on this, you will overwrite
-	/// the event listeners from `ws_stream_wasm`, and things will break.
+	/// Closing the [WsWriter] closes the connection. Otherwise the connection is closed when both halves have
+	/// been dropped. When only the [WsReader] is dropped, incoming messages are discarded.
 	//
-	pub fn wrapped( &self ) -> &WebSocket
+	pub fn split( self ) -> ( WsReader, WsWriter )
 	{
-		&self.ws
+		( self.reader, self.writer )
 	}
 
 
-	/// Wrap this object in [`IoStream`]. `IoStream` implements `AsyncRead`/`AsyncWrite`/`AsyncBufRead`.
-	/// **Beware**: that this will transparenty include text messages as bytes.
+
+	/// The number of received messages that are buffered, waiting to be consumed through the `Stream` impl.
+	/// This allows monitoring whether the consumer keeps up. See [WsConfig::receive_capacity](crate::WsConfig::receive_capacity)
+	/// to bound the buffer.
 	//
-	pub fn into_io( self ) -> IoStream< WsStreamIo, Vec<u8> >
+	pub fn queue_len( &self ) -> usize
 	{
-		IoStream::new( WsStreamIo::new( self ) )
+		self.reader.queue_len()
 	}
-}
 
 
+	pub(crate) fn is_drained( &self ) -> bool
+	{
+		self.reader.is_drained()
+	}
 
-impl fmt::Debug for WsStream
-{
-	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+
+	pub(crate) fn core( &self ) -> &Rc<Core>
 	{
-		write!( f, "WsStream for connection: {}", self.ws.url() )
+		self.writer.core()
 	}
-}
 
 
 
-impl Drop for WsStream
-{
-	// We don't block here, just tell the browser to close the connection and move on.
+	/// The round trip time measured with the last pong. Only available with a [Heartbeat](crate::Heartbeat),
+	/// after the first pong has been received.
 	//
-	fn drop( &mut self )
+	pub fn round_trip_time( &self ) -> Option<Duration>
 	{
-		match self.ready_state()
-		{
-			WsState::Closing | WsState::Closed => {}
+		self.reader.core().pongs.borrow().rtt()
+	}
 
-			_ =>
-			{
-				// This can't fail. Only exceptions are related to invalid
-				// close codes and reason strings to long.
-				//
-				self.ws.close().expect( "WsStream::drop - close ws socket" );
 
 
-				// Notify Observers. This event is not emitted by the websocket API.
-				//
-				notify( self.pharos.clone(), WsEvent::Closing )
-			}
-		}
-
-		self.ws.set_onmessage( None );
-		self.ws.set_onerror  ( None );
-		self.ws.set_onopen   ( None );
-		self.ws.set_onclose  ( None );
+	/// Verify the [WsState] of the connection.
+	//
+	pub fn ready_state( &self ) -> WsState
+	{
+		self.reader.ready_state()
 	}
-}
-
 
 
-impl Stream for WsStream
-{
-	type Item = WsMessage;
 
-	// Currently requires an unfortunate copy from Js memory to WASM memory. Hopefully one
-	// day we will be able to receive the MessageEvt directly in WASM.
+	/// A cloneable handle to send on this connection without exclusive access. See [WsSender].
 	//
-	fn poll_next( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	pub fn sender( &self ) -> WsSender
 	{
-		// Once the queue is empty, check the state of the connection.
-		// When it is closing or closed, no more messages will arrive, so
-		// return Poll::Ready( None )
-		//
-		if self.queue.borrow().is_empty()
-		{
-			*self.waker.borrow_mut() = Some( cx.waker().clone() );
-
-			match self.ready_state()
-			{
-				WsState::Open | WsState::Connecting => Poll::Pending ,
-				_                                   => None.into()   ,
-			}
-		}
-
-		// As long as there is things in the queue, just keep reading
-		//
-		else { self.queue.borrow_mut().pop_front().into() }
+		self.writer.sender()
 	}
-}
 
 
 
-impl Sink<WsMessage> for WsStream
-{
-	type Error = WsErr;
+	/// Access the wrapped [web_sys::WebSocket](https://docs.rs/web-sys/0.3.25/web_sys/struct.WebSocket.html) directly.
+	///
+	/// _ws_stream_wasm_ tries to expose all useful functionality through an idiomatic rust API, so hopefully
+	/// you won't need this, however if I missed something, you can.
+	///
+	/// ## Caveats
+	/// If you call `set_onopen`, `set_onerror`, `set_onmessage` or `set_onclose` on this, you will overwrite
+	/// the event listeners from `ws_stream_wasm`, and things will break.
+	//
+	pub fn wrapped( &self ) -> &WebSocket
+	{
+		self.reader.core().ws()
+	}
 
 
-	// Web API does not really seem to let us check for readiness, other than the connection state.
+	/// Unregister the callbacks of _ws_stream_wasm_ and hand back the underlying `WebSocket` without closing
+	/// the connection. This is the counterpart of [WsMeta::from_websocket](crate::WsMeta::from_websocket).
+	///
+	/// Messages that were received but not yet consumed are dropped. Since no more events are received, observers
+	/// of [WsMeta](crate::WsMeta) won't receive any more events either.
 	//
-	fn poll_ready( self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	pub fn into_inner( self ) -> WebSocket
 	{
-		match self.ready_state()
-		{
-			WsState::Connecting =>
-			{
-				*self.sink_waker.borrow_mut() = Some( cx.waker().clone() );
+		let core = self.reader.core();
 
-				Poll::Pending
-			}
+		core.settle_outbox();
+		core.unregister();
+		core.released.set( true );
 
-			WsState::Open => Ok(()).into(),
-			_             => Err( WsErr::ConnectionNotOpen ).into(),
-		}
+		(**core.ws()).clone()
 	}
 
 
-	fn start_send( self: Pin<&mut Self>, item: WsMessage ) -> Result<(), Self::Error>
+	/// A `Stream` over the incoming messages that doesn't copy binary data into WASM memory. See [JsMessage].
+	/// Messages are taken from the same queue as the `Stream` impl of `WsStream`.
+	//
+	pub fn js_messages( &mut self ) -> JsMessages<'_>
 	{
-		match self.ready_state()
-		{
-			WsState::Open =>
-			{
-				// The send method can return 2 errors:
-				// - unpaired surrogates in UTF (we shouldn't get those in rust strings)
-				// - connection is already closed.
-				//
-				// So if this returns an error, we will return ConnectionNotOpen. In principle
-				// we just checked that it's open, but this guarantees correctness.
-				//
-				match item
-				{
-					WsMessage::Binary( d ) => self.ws.send_with_u8_array( &d ).map_err( |_| WsErr::ConnectionNotOpen )? ,
-					WsMessage::Text  ( s ) => self.ws.send_with_str     ( &s ).map_err( |_| WsErr::ConnectionNotOpen )? ,
-				}
-
-				Ok(())
-			},
+		self.reader.js_messages()
+	}
 
 
-			// Connecting, Closing or Closed
-			//
-			_ => Err( WsErr::ConnectionNotOpen ),
-		}
+	/// A `Stream` over the incoming messages that yields decoding errors in-band and ends with
+	/// [WsErr::ConnectionLost] when the connection is lost. See [TryMessages]. Messages are taken from the
+	/// same queue as the `Stream` impl of `WsStream`.
+	//
+	pub fn try_messages( &mut self ) -> TryMessages<'_>
+	{
+		self.reader.try_messages()
 	}
 
 
-
-	fn poll_flush( self: Pin<&mut Self>, _: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	/// A `Stream` over the incoming messages and the events of the connection, in the order the browser reported
+	/// them. See [WsItems]. Messages are taken from the same queue as the `Stream` impl of `WsStream`. Repeated
+	/// [WsEvent::Pong] and [WsErr::ReceiveQueueFull] events between two messages are coalesced into one.
+	//
+	pub fn items( &mut self ) -> WsItems<'_>
 	{
-		Ok(()).into()
+		self.reader.items()
 	}
 
 
-
-	// TODO: find a simpler implementation, notably this needs to spawn a future.
-	//       this can be done by creating a custom future. If we are going to implement
-	//       events with pharos, that's probably a good time to re-evaluate this.
+	/// Wrap this object in [`IoStream`]. `IoStream` implements `AsyncRead`/`AsyncWrite`/`AsyncBufRead`.
+	/// **Beware**: that this will transparenty include text messages as bytes.
 	//
-	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	pub fn into_io( self ) -> IoStream< WsStreamIo, Vec<u8> >
 	{
-		let state = self.ready_state();
+		IoStream::new( WsStreamIo::new( self ) )
+	}
+}
 
 
-		// First close the inner connection
-		//
-		if state == WsState::Connecting
-		|| state == WsState::Open
-		{
-			// Can't fail
-			//
-			self.ws.close().unwrap_throw();
 
-			notify( self.pharos.clone(), WsEvent::Closing );
-		}
+impl fmt::Debug for WsStream
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "WsStream for connection: {}", self.wrapped().url() )
+	}
+}
 
 
-		// Check whether it's closed
-		//
-		match state
-		{
-			WsState::Closed => Ok(()).into(),
 
-			_ =>
-			{
-				// Create a future that will resolve with the close event, so we can poll it.
-				//
-				if self.closer.is_none()
-				{
-					let mut ph = self.pharos.clone();
+impl Stream for WsStream
+{
+	type Item = WsMessage;
 
-					let closer = async move
-					{
-						let mut rx = match ph.observe( Filter::Pointer( WsEvent::is_closed ).into() ).await
-						{
-							Ok(events) => events                    ,
-							Err(e)     => unreachable!( "{:?}", e ) , // only happens if we closed it.
-						};
+	fn poll_next( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Option< Self::Item >>
+	{
+		Pin::new( &mut self.reader ).poll_next( cx )
+	}
+}
 
-						rx.next().await;
-					};
 
-					self.closer = Some(SendWrapper::new( closer.boxed() ));
-				}
 
+impl Sink<WsMessage> for WsStream
+{
+	type Error = WsErr;
 
-				ready!( self.closer.as_mut().unwrap().as_mut().poll(cx) );
 
-				Ok(()).into()
-			}
-		}
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.writer ).poll_ready( cx )
 	}
-}
 
 
+	fn start_send( mut self: Pin<&mut Self>, item: WsMessage ) -> Result<(), Self::Error>
+	{
+		Pin::new( &mut self.writer ).start_send( item )
+	}
+
 
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.writer ).poll_flush( cx )
+	}
 
+
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		Pin::new( &mut self.writer ).poll_close( cx )
+	}
+}
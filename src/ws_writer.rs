@@ -0,0 +1,284 @@
+use crate::{ import::*, delay::Delay, ws_meta::force_closed, ws_stream::Core, * };
+
+
+/// The sending half of a [WsStream], obtained with [WsStream::split]. A `Sink` of [WsMessage].
+///
+/// Closing the writer through `Sink::poll_close` closes the connection, which also ends the [WsReader]. Dropping
+/// the writer alone doesn't close the connection. It is closed once both halves are dropped.
+//
+pub struct WsWriter
+{
+	core: SendWrapper< Rc<Core> >,
+
+	// Last waker of task that wants to write to the Sink
+	//
+	sink_waker: SendWrapper< Rc<RefCell< Option<Waker> >> >,
+
+	// This allows us to store a future to poll when Sink::poll_close is called
+	//
+	closer: Option<SendWrapper< Pin<Box< dyn Future< Output=() > >> >>,
+
+	// How long poll_close waits for the close event.
+	//
+	close_timeout: Option<Duration>,
+
+	// The high and low watermarks for bufferedAmount, if backpressure is enabled.
+	//
+	watermarks: Option<(u32, u32)>,
+
+	// How often to check bufferedAmount while waiting for it to drain.
+	//
+	drain_interval: Duration,
+
+	// Set when bufferedAmount went over the high watermark, until it drops below the low watermark.
+	//
+	draining: bool,
+
+	// Timer to check bufferedAmount again while waiting for it to drain.
+	//
+	drain_timer: Option<SendWrapper< Delay >>,
+}
+
+
+
+impl WsWriter
+{
+	pub(crate) fn new
+	(
+		core      : SendWrapper< Rc<Core> >                     ,
+		sink_waker: SendWrapper< Rc<RefCell< Option<Waker> >> > ,
+		config    : &WsConfig                                   ,
+	)
+		-> Self
+	{
+		Self
+		{
+			core                                   ,
+			sink_waker                             ,
+			closer        : None                   ,
+			close_timeout : config.close_timeout   ,
+			watermarks    : config.send_watermarks ,
+			drain_interval: config.drain_interval  ,
+			draining      : false                  ,
+			drain_timer   : None                   ,
+		}
+	}
+
+
+	pub(crate) fn core( &self ) -> &Rc<Core>
+	{
+		&self.core
+	}
+
+
+	/// Verify the [WsState] of the connection.
+	//
+	pub fn ready_state( &self ) -> WsState
+	{
+		self.core.ready_state()
+	}
+
+
+	/// A cloneable handle to send on this connection without exclusive access. See [WsSender].
+	//
+	pub fn sender( &self ) -> WsSender
+	{
+		WsSender::new( &self.core, self.watermarks, self.drain_interval )
+	}
+
+
+	// Wait until bufferedAmount is at most `target`. The browser has no event for this, so we poll it on a timer.
+	//
+	fn poll_drain( &mut self, cx: &mut Context<'_>, target: u32 ) -> Poll<Result<(), WsErr>>
+	{
+		loop
+		{
+			// Messages in the outbox are sent when the connection opens.
+			//
+			if self.ready_state() == WsState::Connecting
+			{
+				*self.sink_waker.borrow_mut() = Some( cx.waker().clone() );
+				return Poll::Pending;
+			}
+
+			if self.ready_state() != WsState::Open
+			{
+				self.drain_timer = None;
+				return Err( WsErr::ConnectionNotOpen ).into();
+			}
+
+			if self.core.ws().buffered_amount() <= target
+			{
+				self.drain_timer = None;
+				return Ok(()).into();
+			}
+
+			let interval = self.drain_interval;
+			let timer    = self.drain_timer.get_or_insert_with( || SendWrapper::new( Delay::new( interval ) ) );
+
+			ready!( Pin::new( &mut **timer ).poll( cx ) );
+
+			self.drain_timer = None;
+		}
+	}
+}
+
+
+
+impl fmt::Debug for WsWriter
+{
+	fn fmt( &self, f: &mut fmt::Formatter<'_> ) -> fmt::Result
+	{
+		write!( f, "WsWriter for connection: {}", self.core.ws().url() )
+	}
+}
+
+
+
+impl Sink<WsMessage> for WsWriter
+{
+	type Error = WsErr;
+
+
+	// Web API does not really seem to let us check for readiness, other than the connection state and
+	// bufferedAmount if backpressure is enabled.
+	//
+	fn poll_ready( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		match self.ready_state()
+		{
+			WsState::Connecting if self.core.outbox_has_room() => Ok(()).into(),
+
+			WsState::Connecting =>
+			{
+				*self.sink_waker.borrow_mut() = Some( cx.waker().clone() );
+
+				Poll::Pending
+			}
+
+			WsState::Open => match self.watermarks
+			{
+				None => Ok(()).into(),
+
+				Some(( high, low )) =>
+				{
+					if !self.draining && self.core.ws().buffered_amount() > high
+					{
+						self.draining = true;
+					}
+
+					if self.draining
+					{
+						ready!( self.poll_drain( cx, low ) )?;
+						self.draining = false;
+					}
+
+					Ok(()).into()
+				}
+			}
+
+			_ => Err( WsErr::ConnectionNotOpen ).into(),
+		}
+	}
+
+
+	fn start_send( self: Pin<&mut Self>, item: WsMessage ) -> Result<(), Self::Error>
+	{
+		self.core.send( item )
+	}
+
+
+
+	// Without backpressure, we consider everything flushed once handed to the browser. With backpressure,
+	// we wait until the browser has transmitted everything.
+	//
+	fn poll_flush( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		if self.watermarks.is_none()
+		{
+			return Ok(()).into();
+		}
+
+		self.poll_drain( cx, 0 )
+	}
+
+
+
+	// TODO: find a simpler implementation, notably this needs to spawn a future.
+	//       this can be done by creating a custom future. If we are going to implement
+	//       events with pharos, that's probably a good time to re-evaluate this.
+	//
+	fn poll_close( mut self: Pin<&mut Self>, cx: &mut Context<'_> ) -> Poll<Result<(), Self::Error>>
+	{
+		let state = self.ready_state();
+
+
+		// First close the inner connection
+		//
+		if state == WsState::Connecting
+		|| state == WsState::Open
+		{
+			// Can't fail
+			//
+			self.core.close_ws().unwrap_throw();
+
+			self.core.notifier().notify( WsEvent::Closing );
+		}
+
+
+		// Check whether it's closed
+		//
+		match state
+		{
+			WsState::Closed => Ok(()).into(),
+
+			// We only unregister the close callback while alive when the close timeout expired,
+			// in which case the browser might never report the connection as closed.
+			//
+			_ if self.core.ws().onclose().is_none() => Ok(()).into(),
+
+			_ =>
+			{
+				// Create a future that will resolve with the close event, so we can poll it.
+				//
+				if self.closer.is_none()
+				{
+					let no      = self.core.notifier().clone();
+					let ws      = self.core.ws().clone();
+					let timeout = self.close_timeout;
+
+					let mut rx = match no.observe( no.options( Filter::Pointer( WsEvent::is_closed ) ) )
+					{
+						Ok(events) => events                    ,
+						Err(e)     => unreachable!( "{:?}", e ) , // only happens if we closed it.
+					};
+
+					let closer = async move
+					{
+						match timeout
+						{
+							None => { rx.next().await; }
+
+							Some( timeout ) =>
+							{
+								if let Either::Right(_) = future::select( rx.next(), Delay::new( timeout ) ).await
+								{
+									force_closed( &ws, &no, CloseInitiator::Local );
+								}
+							}
+						}
+					};
+
+					self.closer = Some(SendWrapper::new( closer.boxed_local() ));
+				}
+
+
+				ready!( self.closer.as_mut().unwrap().as_mut().poll(cx) );
+
+				self.closer = None;
+
+				Ok(()).into()
+			}
+		}
+	}
+}
@@ -0,0 +1,188 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests connect to an echo server which just bounces back all data.
+//
+// ✔ Verify the provider can change the query string of the url
+// ✔ Verify an error of the provider is returned and can be downcast to its type
+// ✔ Verify the configuration is validated before calling the provider
+// ✔ Verify the provider is asked to refresh after the server closed with a code that rejects the credentials
+//
+// The refresh test needs the servers from ci/test_server.py.
+//
+use
+{
+	futures::prelude      :: *                        ,
+	log                   :: *                        ,
+	pharos                :: *                        ,
+	std                   :: { rc::Rc, cell::RefCell } ,
+	std::time             :: Duration                 ,
+	wasm_bindgen::prelude :: *                        ,
+	wasm_bindgen_test     :: *                        ,
+	ws_stream_wasm        :: *                        ,
+};
+
+
+
+const URL  : &str = "ws://127.0.0.1:3212/"     ;
+const CLOSE: &str = "ws://127.0.0.1:3315/4001" ;
+
+
+
+#[ derive( Debug, PartialEq, Eq ) ]
+//
+struct NoToken;
+
+impl std::fmt::Display for NoToken
+{
+	fn fmt( &self, f: &mut std::fmt::Formatter<'_> ) -> std::fmt::Result
+	{
+		write!( f, "no token" )
+	}
+}
+
+impl std::error::Error for NoToken {}
+
+
+
+// Verify the provider can change the query string of the url.
+//
+#[ wasm_bindgen_test ]
+//
+async fn set_query()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: set_query" );
+
+	let config = WsMeta::builder( URL ).connect_params( |mut params: ConnectParams| async move
+	{
+		params.set_query( "token", "secret" );
+		Ok( params )
+	});
+
+	let (ws, mut wsio) = config.connect().await.expect_throw( "Could not create websocket" );
+
+	assert_eq!( "ws://127.0.0.1:3212/?token=secret", ws.url() );
+
+	let message = WsMessage::Text( "Hello from browser".to_string() );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), wsio.next().await );
+}
+
+
+
+// Verify an error of the provider is returned and can be downcast to its type.
+//
+#[ wasm_bindgen_test ]
+//
+async fn provider_error()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: provider_error" );
+
+	let config = WsMeta::builder( URL ).connect_params( |_| async { Err( NoToken.into() ) } );
+
+	let err = match config.connect().await.unwrap_err()
+	{
+		WsErr::ConnectParams{ error } => error,
+		e                            => panic!( "unexpected error: {:?}", e ),
+	};
+
+	assert_eq!( Some( &NoToken ), err.downcast_ref::<NoToken>() );
+}
+
+
+
+// The provider is not called with an invalid configuration.
+//
+#[ wasm_bindgen_test ]
+//
+async fn validate_first()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: validate_first" );
+
+	let config = WsMeta::builder( URL ).drop_close_code( 1001 ).connect_params( |_| async
+	{
+		panic!( "the provider should not be called" )
+	});
+
+	assert_eq!( WsErr::InvalidCloseCode{ supplied: 1001 }, config.connect().await.unwrap_err() );
+}
+
+
+
+// Verify the provider is asked to refresh after the server closed with a code that rejects the credentials.
+// The server closes every connection with 4001, so the fresh credentials are for the echo server, which
+// accepts them.
+//
+#[ wasm_bindgen_test ]
+//
+async fn refresh()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: refresh" );
+
+	let refreshes = Rc::new( RefCell::new( Vec::new() ) );
+	let record    = refreshes.clone();
+
+	let config = WsMeta::builder( CLOSE )
+
+		.credentials_rejected_on( vec![ 4001 ] )
+
+		.connect_params( move |mut params: ConnectParams|
+		{
+			record.borrow_mut().push( params.refresh() );
+
+			if params.refresh()
+			{
+				params.set_url( URL );
+			}
+
+			async move { Ok( params ) }
+		})
+	;
+
+	let reconnect = ReconnectConfig::default().initial_delay( Duration::from_millis( 10 ) );
+
+	let (mut ws, mut wsio) = ReconnectingWsMeta::connect_with( config, reconnect ).await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+	match evts.next().await.unwrap_throw()
+	{
+		WsEvent::Closed( evt ) =>
+		{
+			assert!( evt.was_clean );
+			assert_eq!( 4001, evt.code );
+		}
+
+		evt => panic!( "unexpected event: {:?}", evt ),
+	}
+
+	assert_eq!( WsEvent::Reconnecting{ attempt: 1 }, evts.next().await.unwrap_throw() );
+	assert!( evts.next().await.unwrap_throw().is_reconnected() );
+
+	assert_eq!( vec![ false, true ], *refreshes.borrow() );
+	assert_eq!( URL, ws.url() );
+
+	let message = WsMessage::Text( "Hello from browser".to_string() );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), wsio.next().await );
+
+	ws.close().await.expect_throw( "close ws" );
+}
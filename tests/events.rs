@@ -5,15 +5,18 @@ wasm_bindgen_test_configure!(run_in_browser);
 // Tests send to an echo server which just bounces back all data.
 //
 // ✔ Verify close events are emitted.
+// ✔ Verify observers receive events synchronously, before the code that caused them continues.
+// ✔ Verify an observer with a full channel receives the same events in the same order as other observers.
 //
 use
 {
-	futures::prelude      :: { *                         } ,
-	wasm_bindgen::prelude :: { *                         } ,
-	wasm_bindgen_test     :: { *                         } ,
-	log                   :: { *                         } ,
-	ws_stream_wasm        :: { *                         } ,
-	pharos                :: { ObserveConfig, Observable } ,
+	futures::prelude      :: { *                                  } ,
+	wasm_bindgen::prelude :: { *                                  } ,
+	wasm_bindgen_test     :: { *                                  } ,
+	log                   :: { *                                  } ,
+	ws_stream_wasm        :: { *                                  } ,
+	pharos                :: { ObserveConfig, Observable, Channel } ,
+	std::time             :: { Duration                           } ,
 	// web_sys               :: { console::log_1 as dbg } ,
 };
 
@@ -42,3 +45,86 @@ async fn close_events()
 	assert!( evts.next().await.unwrap_throw().is_closed()  );
 }
 
+
+
+
+// Verify observers receive events synchronously, before the code that caused them continues.
+//
+#[ wasm_bindgen_test ]
+//
+async fn synchronous()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: synchronous" );
+
+	let (mut ws, wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	let mut evts = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+	// Closes the connection.
+	//
+	drop( wsio );
+
+	assert_eq!( Some( Some( WsEvent::Closing ) ), evts.next().now_or_never() );
+}
+
+
+
+// Verify an observer with a full channel receives the same events in the same order as other observers.
+//
+#[ wasm_bindgen_test ]
+//
+async fn bounded_in_order()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: bounded_in_order" );
+
+	// The echo server sends the ping back as its own pong, so we get a steady flow of pong events.
+	//
+	let heartbeat = Heartbeat::default()
+
+		.interval( Duration::from_millis( 10 )                                           )
+		.ping    ( WsMessage::Text( "heartbeat".to_string() )                            )
+		.is_pong ( |msg| matches!( msg, JsMessage::Text( text ) if text == "heartbeat" ) )
+	;
+
+	let (mut ws, _wsio) = WsMeta::builder( URL ).heartbeat( heartbeat ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut all     = ws.observe( ObserveConfig::default()     ).await.expect( "observe" );
+	let mut bounded = ws.observe( Channel::Bounded( 1 ).into() ).await.expect( "observe" );
+
+	// Don't read from the bounded observer until more events happened than fit in its channel.
+	//
+	let mut expect = Vec::new();
+
+	while expect.len() < 5
+	{
+		expect.push( all.next().await.expect_throw( "event" ) );
+	}
+
+	ws.close().await.expect_throw( "close ws" );
+
+	while let Some( evt ) = all.next().await
+	{
+		let closed = evt.is_closed();
+
+		expect.push( evt );
+
+		if closed { break; }
+	}
+
+	let mut got = Vec::new();
+
+	while got.len() < expect.len()
+	{
+		got.push( bounded.next().await.expect_throw( "event" ) );
+	}
+
+	assert!( expect.last().unwrap_throw().is_closed() );
+	assert_eq!( expect, got );
+}
@@ -0,0 +1,123 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests connect to an echo server which just bounces back all data.
+//
+// ✔ Verify the endpoints are tried in order and the one that accepted is reported
+// ✔ Verify the error of every endpoint is reported when all fail
+// ✔ Verify a staggered parallel attempt wins over an endpoint that doesn't respond
+// ✔ Verify an invalid configuration is reported right away
+//
+use
+{
+	futures::prelude      :: *        ,
+	log                   :: *        ,
+	std::time             :: Duration ,
+	wasm_bindgen::prelude :: *        ,
+	wasm_bindgen_test     :: *        ,
+	ws_stream_wasm        :: *        ,
+};
+
+
+
+const URL       : &str = "ws://127.0.0.1:3212/" ;
+const WRONG_PORT: &str = "ws://127.0.0.1:33212/";
+
+// The server of ci/test_server.py on this port accepts the TCP connection but never answers the handshake.
+//
+const HANG: &str = "ws://127.0.0.1:3313/";
+
+
+
+// Verify the endpoints are tried in order and the one that accepted is reported.
+//
+#[ wasm_bindgen_test ]
+//
+async fn in_order()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: in_order" );
+
+	let (_ws, mut wsio, winner) = Failover::new( vec![ WRONG_PORT, URL ] ).connect().await
+
+		.expect_throw( "connect to the second endpoint" )
+	;
+
+	assert_eq!( 1, winner );
+
+	let message = WsMessage::Text( "Hello from browser".to_string() );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), wsio.next().await );
+}
+
+
+
+// Verify the error of every endpoint is reported when all fail.
+//
+#[ wasm_bindgen_test ]
+//
+async fn all_failed()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: all_failed" );
+
+	let err = Failover::new( vec![ WRONG_PORT, "ftp://127.0.0.1:3212/" ] ).connect().await.unwrap_err();
+
+	let errors = match err
+	{
+		WsErr::AllEndpointsFailed{ errors } => errors,
+		e                                   => panic!( "unexpected error: {:?}", e ),
+	};
+
+	assert_eq!( 2, errors.len() );
+
+	assert_eq!( WRONG_PORT, errors[0].0 );
+	assert!( matches!( errors[0].1, WsErr::ConnectionFailed{..} ) );
+
+	assert_eq!( "ftp://127.0.0.1:3212/", errors[1].0 );
+	assert!( matches!( errors[1].1, WsErr::InvalidUrl{..} ) );
+}
+
+
+
+// Verify a staggered parallel attempt wins over an endpoint that doesn't respond.
+//
+#[ wasm_bindgen_test ]
+//
+async fn parallel()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: parallel" );
+
+	let failover = Failover::new( vec![ HANG, URL ] ).parallel( 2, Duration::from_millis( 50 ) );
+
+	let (_ws, _wsio, winner) = failover.connect().await.expect_throw( "connect to the second endpoint" );
+
+	assert_eq!( 1, winner );
+}
+
+
+
+// Verify an invalid configuration is reported right away.
+//
+#[ wasm_bindgen_test ]
+//
+async fn invalid_config()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: invalid_config" );
+
+	let config = WsConfig::new( "" ).drop_close_code( 1001 );
+	let err    = Failover::new( vec![ URL ] ).connect_with( config ).await.unwrap_err();
+
+	assert_eq!( WsErr::InvalidCloseCode{ supplied: 1001 }, err );
+}
@@ -111,6 +111,7 @@ async fn echo( name: &str, size: usize, data: Bytes )
 /////////////////////
 
 
+#[ allow( dead_code ) ]
 #[ derive( Debug, Clone, Serialize, Deserialize, PartialEq, Eq ) ]
 //
 struct Data
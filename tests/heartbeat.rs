@@ -0,0 +1,151 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests send to an echo server which just bounces back all data, so the ping comes back as its own pong.
+// The connection_lost test needs the servers from ci/test_server.py.
+//
+// ✔ Verify pongs are reported with their round trip time and not passed to the consumer
+// ✔ Verify the connection is closed with the configured code when pongs stop arriving
+// ✔ Verify try_messages reports a heartbeat timeout as a lost connection
+//
+use
+{
+	futures::prelude      :: *           ,
+	log                   :: *           ,
+	pharos                :: *           ,
+	std::time             :: Duration    ,
+	wasm_bindgen::prelude :: *           ,
+	wasm_bindgen_test     :: *           ,
+	ws_stream_wasm        :: *           ,
+};
+
+
+
+const URL   : &str = "ws://127.0.0.1:3212/";
+const SILENT: &str = "ws://127.0.0.1:3314/";
+
+
+
+// Verify pongs are reported with their round trip time and not passed to the consumer.
+//
+#[ wasm_bindgen_test ]
+//
+async fn pong()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: pong" );
+
+	let heartbeat = Heartbeat::default()
+
+		.interval( Duration::from_millis( 10 )                                           )
+		.ping    ( WsMessage::Text( "heartbeat".to_string() )                            )
+		.is_pong ( |msg| matches!( msg, JsMessage::Text( text ) if text == "heartbeat" ) )
+	;
+
+	let (mut ws, mut wsio) = WsMeta::builder( URL ).heartbeat( heartbeat ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_pong ).into() ).await.expect( "observe" );
+
+	assert!( evts.next().await.unwrap_throw().is_pong() );
+	assert!( wsio.round_trip_time().is_some() );
+
+	let message = WsMessage::Text( "Hello from browser".to_string() );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), wsio.next().await );
+}
+
+
+
+// Verify the connection is closed with the configured code when pongs stop arriving.
+//
+#[ wasm_bindgen_test ]
+//
+async fn timeout()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: timeout" );
+
+	let heartbeat = Heartbeat::default()
+
+		.interval  ( Duration::from_millis( 10 ) )
+		.timeout   ( Duration::from_millis( 10 ) )
+		.is_pong   ( |_| false                   )
+		.close_code( 4000                        )
+	;
+
+	let (mut ws, _wsio) = WsMeta::builder( URL ).heartbeat( heartbeat ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let filter   = Filter::Pointer( |evt: &WsEvent| evt.is_heartbeat_timeout() || evt.is_closed() );
+	let mut evts = ws.observe( filter.into() ).await.expect( "observe" );
+
+	assert_eq!( Some( WsEvent::HeartbeatTimeout ), evts.next().await );
+
+	match evts.next().await.unwrap_throw()
+	{
+		WsEvent::Closed( evt ) =>
+		{
+			assert_eq!( 4000                     , evt.code      );
+			assert_eq!( CloseInitiator::Heartbeat, evt.initiator );
+		}
+
+		evt => panic!( "unexpected event: {:?}", evt ),
+	}
+}
+
+
+
+// Verify try_messages reports a heartbeat timeout as a lost connection. The server never answers, neither the pings
+// nor the close frame, so the close is synthesized after the close timeout.
+//
+#[ wasm_bindgen_test ]
+//
+async fn connection_lost()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connection_lost" );
+
+	let heartbeat = Heartbeat::default()
+
+		.interval( Duration::from_millis( 10 ) )
+		.timeout ( Duration::from_millis( 10 ) )
+	;
+
+	let (_ws, mut wsio) = WsMeta::builder( SILENT )
+
+		.heartbeat    ( heartbeat                    )
+		.close_timeout( Duration::from_millis( 100 ) )
+		.connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut msgs = wsio.try_messages();
+
+	match msgs.next().await
+	{
+		Some( Err( WsErr::ConnectionLost{ event } ) ) =>
+		{
+			assert!( !event.was_clean );
+			assert_eq!( 1006                     , event.code      );
+			assert_eq!( CloseInitiator::Heartbeat, event.initiator );
+		}
+
+		item => panic!( "unexpected item: {:?}", item ),
+	}
+
+	assert!( msgs.next().await.is_none() );
+}
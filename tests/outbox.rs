@@ -0,0 +1,136 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests send to an echo server which just bounces back all data.
+//
+// ✔ Verify messages sent whilst connecting are sent in order when the connection opens
+// ✔ Verify a full outbox is reported by try_send and makes the Sink wait for the connection to open
+// ✔ Verify queued messages are reported when the connection fails with OutboxPolicy::Report
+// ✔ Verify an invalid config is rejected by from_websocket_config
+//
+use
+{
+	futures::prelude      :: *         ,
+	log                   :: *         ,
+	pharos                :: *         ,
+	wasm_bindgen::prelude :: *         ,
+	wasm_bindgen_test     :: *         ,
+	web_sys               :: WebSocket ,
+	ws_stream_wasm        :: *         ,
+};
+
+
+
+const URL: &str = "ws://127.0.0.1:3212/";
+
+
+
+fn text( s: &str ) -> WsMessage
+{
+	WsMessage::Text( s.to_string() )
+}
+
+
+
+// Verify messages sent whilst connecting are sent in order when the connection opens.
+//
+#[ wasm_bindgen_test ]
+//
+async fn flush_on_open()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: flush_on_open" );
+
+	let raw             = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let config          = WsMeta::builder( URL ).outbox( 10, OutboxPolicy::Discard );
+	let (_ws, mut wsio) = WsMeta::from_websocket_config( raw, config ).expect_throw( "wrap websocket" );
+
+	assert_eq!( WsState::Connecting, wsio.ready_state() );
+
+	wsio.send( text( "a" ) ).await.expect_throw( "queue a" );
+	wsio.send( text( "b" ) ).await.expect_throw( "queue b" );
+
+	assert_eq!( WsState::Connecting, wsio.ready_state() );
+
+	assert_eq!( Some( text( "a" ) ), wsio.next().await );
+	assert_eq!( Some( text( "b" ) ), wsio.next().await );
+}
+
+
+
+// Verify a full outbox is reported by try_send and makes the Sink wait for the connection to open.
+//
+#[ wasm_bindgen_test ]
+//
+async fn full()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: full" );
+
+	let raw             = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let config          = WsMeta::builder( URL ).outbox( 1, OutboxPolicy::Discard );
+	let (_ws, mut wsio) = WsMeta::from_websocket_config( raw, config ).expect_throw( "wrap websocket" );
+	let sender          = wsio.sender();
+
+	sender.try_send( text( "a" ) ).expect_throw( "queue a" );
+
+	assert_eq!( Err( WsErr::OutboxFull ), sender.try_send( text( "b" ) ) );
+
+	// Waits for the connection to open.
+	//
+	wsio.send( text( "c" ) ).await.expect_throw( "send c" );
+
+	assert_eq!( WsState::Open, wsio.ready_state() );
+
+	assert_eq!( Some( text( "a" ) ), wsio.next().await );
+	assert_eq!( Some( text( "c" ) ), wsio.next().await );
+}
+
+
+
+// Verify queued messages are reported when the connection fails with OutboxPolicy::Report.
+//
+#[ wasm_bindgen_test ]
+//
+async fn report()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: report" );
+
+	let url                = "ws://127.0.0.1:33212/";
+	let raw                = WebSocket::new( url ).expect_throw( "create raw websocket" );
+	let config             = WsMeta::builder( url ).outbox( 10, OutboxPolicy::Report );
+	let (mut ws, mut wsio) = WsMeta::from_websocket_config( raw, config ).expect_throw( "wrap websocket" );
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_undelivered ).into() ).await.expect( "observe" );
+
+	wsio.send( text( "a" ) ).await.expect_throw( "queue a" );
+	wsio.send( text( "b" ) ).await.expect_throw( "queue b" );
+
+	assert_eq!( Some( WsEvent::Undelivered( vec![ text( "a" ), text( "b" ) ] ) ), evts.next().await );
+	assert_eq!( None, wsio.next().await );
+}
+
+
+
+// Verify an invalid config is rejected by from_websocket_config.
+//
+#[ wasm_bindgen_test ]
+//
+async fn invalid_config()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: invalid_config" );
+
+	let raw    = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let config = WsMeta::builder( URL ).drop_close_code( 1001 );
+
+	assert_eq!( WsErr::InvalidCloseCode{ supplied: 1001 }, WsMeta::from_websocket_config( raw, config ).unwrap_err() );
+}
@@ -0,0 +1,236 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests send to an echo server which just bounces back all data. The give_up test needs the servers from
+// ci/test_server.py.
+//
+// ✔ Verify that ReconnectingWsStream and ReconnectingWsMeta are Send
+// ✔ Verify a lost connection is re-established and emits the reconnection events
+// ✔ Verify messages sent while reconnecting are sent in order on the new connection
+// ✔ Verify closing from ReconnectingWsMeta doesn't reconnect
+// ✔ Verify the stream ends when retry_if rejects the close event
+// ✔ Verify the delay grows between attempts and the stream ends after max_attempts
+//
+use
+{
+	futures::prelude      :: *           ,
+	log                   :: *           ,
+	pharos                :: *           ,
+	std::marker           :: PhantomData ,
+	std::time             :: Duration    ,
+	wasm_bindgen::prelude :: *           ,
+	wasm_bindgen_test     :: *           ,
+	ws_stream_wasm        :: *           ,
+};
+
+
+
+const URL : &str = "ws://127.0.0.1:3212/";
+const ONCE: &str = "ws://127.0.0.1:3316/";
+
+
+fn config() -> ReconnectConfig
+{
+	ReconnectConfig::default().initial_delay( Duration::from_millis( 10 ) )
+}
+
+
+
+// Verify that both ReconnectingWsStream and ReconnectingWsMeta are Send.
+//
+#[ wasm_bindgen_test ]
+//
+fn sendness()
+{
+	struct SendNess<T: Send + Sync>{ _phantom: PhantomData<T> }
+
+	let _x = SendNess::<ReconnectingWsStream>{ _phantom: PhantomData };
+	let _x = SendNess::<ReconnectingWsMeta  >{ _phantom: PhantomData };
+}
+
+
+
+// Verify a lost connection is re-established and emits the reconnection events.
+//
+#[ wasm_bindgen_test ]
+//
+async fn reconnect_after_loss()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: reconnect_after_loss" );
+
+	let (mut ws, mut wsio) = ReconnectingWsMeta::connect( URL, None, config() ).await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+	// Closing the raw socket looks like a lost connection to ws_stream_wasm.
+	//
+	ws.current().wrapped().close().expect_throw( "close raw socket" );
+
+	assert!( evts.next().await.unwrap_throw().is_closed() );
+	assert_eq!( WsEvent::Reconnecting{ attempt: 1 }, evts.next().await.unwrap_throw() );
+	assert!( evts.next().await.unwrap_throw().is_reconnected() );
+
+	let message = b"Hello from browser".to_vec();
+
+	wsio.send( WsMessage::Binary( message.clone() ) ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( WsMessage::Binary( message ) ), wsio.next().await );
+
+	ws.close().await.expect_throw( "close ws" );
+}
+
+
+
+// Verify messages sent while reconnecting are queued in the outbox and sent in order on the new connection.
+//
+#[ wasm_bindgen_test ]
+//
+async fn outbox_while_reconnecting()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: outbox_while_reconnecting" );
+
+	let ws_config = WsMeta::builder( URL ).outbox( 2, OutboxPolicy::Report );
+
+	// Leave time to send before the new connection is established.
+	//
+	let config = ReconnectConfig::default().initial_delay( Duration::from_millis( 100 ) );
+
+	let (mut ws, mut wsio) = ReconnectingWsMeta::connect_with( ws_config, config ).await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( ObserveConfig::default() ).await.expect( "observe" );
+
+	ws.current().wrapped().close().expect_throw( "close raw socket" );
+
+	assert!( evts.next().await.unwrap_throw().is_closed() );
+	assert_eq!( WsEvent::Reconnecting{ attempt: 1 }, evts.next().await.unwrap_throw() );
+	assert_eq!( WsState::Connecting, wsio.ready_state() );
+
+	let first  = WsMessage::Text( "first" .to_string() );
+	let second = WsMessage::Text( "second".to_string() );
+
+	wsio.send( first .clone() ).await.expect_throw( "queue first message"  );
+	wsio.send( second.clone() ).await.expect_throw( "queue second message" );
+
+	assert!( evts.next().await.unwrap_throw().is_reconnected() );
+
+	assert_eq!( Some( first  ), wsio.next().await );
+	assert_eq!( Some( second ), wsio.next().await );
+
+	ws.close().await.expect_throw( "close ws" );
+}
+
+
+
+// Verify closing from ReconnectingWsMeta doesn't reconnect.
+//
+#[ wasm_bindgen_test ]
+//
+async fn close_does_not_reconnect()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: close_does_not_reconnect" );
+
+	let (ws, mut wsio) = ReconnectingWsMeta::connect( URL, None, config() ).await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	ws.close().await.expect_throw( "close ws" );
+
+	assert!( wsio.next().await.is_none() );
+	assert_eq!( WsState::Closed, ws.ready_state() );
+}
+
+
+
+// Verify the stream ends when retry_if rejects the close event.
+//
+#[ wasm_bindgen_test ]
+//
+async fn retry_if_rejects()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: retry_if_rejects" );
+
+	let (ws, mut wsio) = ReconnectingWsMeta::connect( URL, None, config().retry_if( |_| false ) ).await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	ws.current().wrapped().close().expect_throw( "close raw socket" );
+
+	assert!( wsio.next().await.is_none() );
+	assert_eq!( WsState::Closed, ws.ready_state() );
+}
+
+
+
+// Verify the delay grows between attempts and the stream ends after max_attempts. The server accepts the first
+// connection and refuses all later ones, so every reconnection attempt fails.
+//
+#[ wasm_bindgen_test ]
+//
+async fn give_up()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: give_up" );
+
+	let config = ReconnectConfig::default()
+
+		.initial_delay( Duration::from_millis( 50 ) )
+		.multiplier   ( 2.0                         )
+		.jitter       ( 0.0                         )
+		.max_attempts ( 3                           )
+	;
+
+	// The server only accepts the first connection to a path, so use a new one every run.
+	//
+	let url = format!( "{}{}", ONCE, js_sys::Date::now() );
+
+	let (mut ws, mut wsio) = ReconnectingWsMeta::connect( url, None, config ).await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let filter   = Filter::Pointer( WsEvent::is_reconnecting );
+	let mut evts = ws.observe( filter.into() ).await.expect( "observe" );
+
+	let mut attempts = Vec::new();
+
+	for attempt in 1..=3
+	{
+		assert_eq!( WsEvent::Reconnecting{ attempt }, evts.next().await.unwrap_throw() );
+		attempts.push( js_sys::Date::now() );
+	}
+
+	assert!( wsio.next().await.is_none() );
+	assert_eq!( WsState::Closed, ws.ready_state() );
+
+	// No attempt after the last one allowed.
+	//
+	assert!( evts.next().now_or_never().is_none() );
+
+	// Every gap is the delay of the attempt plus the time it took to fail.
+	//
+	let first  = attempts[1] - attempts[0];
+	let second = attempts[2] - attempts[1];
+
+	assert!( first  >=  50.0, "first delay: {}ms" , first  );
+	assert!( second >= 100.0, "second delay: {}ms", second );
+}
@@ -0,0 +1,111 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests send to an echo server which just bounces back all data.
+//
+// ✔ Verify messages are yielded as Ok and the stream ends without error on a clean close
+// ✔ Verify the stream ends with ConnectionLost when the connection was lost without a closing handshake
+// ✔ Verify the stream ends without error when we closed the connection and the closing handshake timed out
+//
+// Decoding failures can't be provoked with the echo server, as it only sends valid messages.
+//
+use
+{
+	futures::prelude      :: *         ,
+	log                   :: *         ,
+	std::time             :: Duration  ,
+	wasm_bindgen::prelude :: *         ,
+	wasm_bindgen_test     :: *         ,
+	web_sys               :: WebSocket ,
+	ws_stream_wasm        :: *         ,
+};
+
+
+
+const URL       : &str = "ws://127.0.0.1:3212/" ;
+const WRONG_PORT: &str = "ws://127.0.0.1:33212/";
+const SILENT    : &str = "ws://127.0.0.1:3314/" ;
+
+
+
+// Verify messages are yielded as Ok and the stream ends without error on a clean close.
+//
+#[ wasm_bindgen_test ]
+//
+async fn clean_close()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: clean_close" );
+
+	let (ws, mut wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let message        = WsMessage::Text( "Hello from browser".to_string() );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( Ok( message ) ), wsio.try_messages().next().await );
+
+	ws.close().await.expect_throw( "close" );
+
+	assert_eq!( None, wsio.try_messages().next().await );
+}
+
+
+
+// Verify the stream ends with ConnectionLost when the connection was lost without a closing handshake. A socket to
+// a port nobody listens on fails without us closing it, like a connection the network dropped.
+//
+#[ wasm_bindgen_test ]
+//
+async fn connection_lost()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connection_lost" );
+
+	let raw             = WebSocket::new( WRONG_PORT ).expect_throw( "create raw websocket" );
+	let (_ws, mut wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+
+	let mut msgs = wsio.try_messages();
+
+	let event = match msgs.next().await
+	{
+		Some( Err( WsErr::ConnectionLost{ event } ) ) => event,
+		other                                        => panic!( "unexpected item: {:?}", other ),
+	};
+
+	assert_eq!( 1006                    , event.code      );
+	assert_eq!( CloseInitiator::Abnormal, event.initiator );
+
+	assert_eq!( None, msgs.next().await );
+}
+
+
+
+// Verify the stream ends without error when we closed the connection and the closing handshake timed out. The
+// server of ci/test_server.py on this port never answers the close frame.
+//
+#[ wasm_bindgen_test ]
+//
+async fn local_close_timeout()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: local_close_timeout" );
+
+	let (ws, mut wsio) = WsMeta::builder( SILENT ).close_timeout( Duration::from_millis( 100 ) ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let event = ws.close().await.expect_throw( "close" );
+
+	assert!( !event.was_clean );
+	assert_eq!( 1006                 , event.code      );
+	assert_eq!( CloseInitiator::Local, event.initiator );
+
+	assert_eq!( None, wsio.try_messages().next().await );
+}
@@ -0,0 +1,85 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests send to an echo server which just bounces back all data.
+//
+// ✔ Verify messages and events are yielded in the order they happened and the stream ends after Closed
+// ✔ Verify the Stream impl of WsStream skips the events that items yields
+//
+use
+{
+	futures::prelude      :: *         ,
+	log                   :: *         ,
+	wasm_bindgen::prelude :: *         ,
+	wasm_bindgen_test     :: *         ,
+	web_sys               :: WebSocket ,
+	ws_stream_wasm        :: *         ,
+};
+
+
+
+const URL: &str = "ws://127.0.0.1:3212/";
+
+
+
+// Verify messages and events are yielded in the order they happened and the stream ends after Closed.
+//
+#[ wasm_bindgen_test ]
+//
+async fn order()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: order" );
+
+	let raw            = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let (ws, mut wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+	let message        = WsMessage::Text( "Hello from browser".to_string() );
+
+	assert_eq!( Some( WsItem::Event( WsEvent::Open ) ), wsio.items().next().await );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( WsItem::Message( message ) ), wsio.items().next().await );
+
+	ws.close().await.expect_throw( "close" );
+
+	let mut items = wsio.items();
+
+	assert_eq!( Some( WsItem::Event( WsEvent::Closing ) ), items.next().await );
+
+	match items.next().await
+	{
+		Some( WsItem::Event( WsEvent::Closed( evt ) ) ) => assert_eq!( CloseInitiator::Local, evt.initiator ),
+		item                                            => panic!( "unexpected item: {:?}", item ),
+	}
+
+	assert_eq!( None, items.next().await );
+}
+
+
+
+// Verify the Stream impl of WsStream skips the events that items yields.
+//
+#[ wasm_bindgen_test ]
+//
+async fn stream_skips_events()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: stream_skips_events" );
+
+	let raw             = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let (_ws, mut wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+	let message         = WsMessage::Text( "Hello from browser".to_string() );
+
+	// Waits for the Open event, which stays in the queue.
+	//
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), wsio.next().await );
+	assert_eq!( 0, wsio.queue_len() );
+}
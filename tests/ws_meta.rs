@@ -9,31 +9,58 @@ wasm_bindgen_test_configure!(run_in_browser);
 // ✔ WsMeta::connect: Verify error when connecting to a forbidden port
 // ✔ WsMeta::connect: Verify error when connecting to wss:// on ws:// server
 // ✔ WsMeta::connect: Verify error when connecting to a wrong scheme
+// ✔ WsMeta::connect: Verify error when connecting to a url with a fragment
+// ✔ WsMeta::connect: Verify http urls and urls relative to the location of the test page are turned into ws urls
+// ✔ WsMeta::connect: Verify error on an invalid or duplicate sub-protocol
+// ✔ WsMeta::connect: Verify SecurityError when connecting to ws:// from a page served over https. Ignored by
+//   default, as the test page is served over http, where the browser allows the connection.
+// ✔ WsMeta::connect_timeout: Verify error when the connection isn't established in time
 // ✔ Verify the state method
 // ✔ Verify closing from WsStream
 // ✔ Verify url method
 // ✔ Verify sending no subprotocols
+// ✔ WsConfig::require_protocol: Verify error when the server doesn't select a protocol, and when none is offered
+// ✔ WsConfig::observer_channel: Verify a bounded channel is used and a channel of size 0 is rejected
+// ✔ WsConfig::subprotocols: Verify typed protocols with duplicate or invalid names are rejected
 //   note: we currently don't have a backend server that supports protocols,
 //   so there is no test for testing usage of protocols
 // ✔ Verify closing with a valid code
 // ✔ Verify error upon closing with invalid code
+// ✔ Verify closing with a typed close code and rejecting codes the browser doesn't allow before closing
+// ✔ Verify the close event of a connection closed from WsMeta or WsStream has a local initiator
 // ✔ Verify closing with a valid code and reason
 // ✔ Verfiy close_reason with an invalid close code
 // ✔ Verfiy close_reason with an invalid reason string
 // ✔ Verfiy Debug impl
+// ✔ WsMeta::builder: Verify error on an invalid drop close code
+// ✔ WsMeta::builder: Verify the drop close code is used when closing from WsStream
+// ✔ WsConfig::close_timeout: Verify close resolves with a synthesized close event when the handshake takes too long
+// ✔ WsConfig::idle_timeout: Verify an idle connection is closed normally after an IdleTimeout event
+// ✔ WsConfig::idle_timeout: Verify error on a reason string that is too long
+// ✔ WsMeta::from_websocket: Verify a connecting socket opens and can send
+// ✔ WsMeta::from_websocket: Verify an open socket reports Open and survives WsStream::into_inner
+// ✔ WsMeta::from_websocket: Verify error on a closing socket
+// ✔ WsMeta::state_watch: Verify the current state is yielded first, followed by every transition
+// ✔ WsMeta::state_watch: Verify a watch created on a connecting socket starts with Connecting
+// ✔ WsMeta::closed: Verify it resolves with the close event, also when called after the connection closed
 //
 use
 {
-	futures               :: { sink::SinkExt      } ,
-	wasm_bindgen::prelude :: { *                  } ,
-	wasm_bindgen_test     :: { *                  } ,
-	ws_stream_wasm        :: { *                  } ,
-	log                   :: { *                  } ,
+	futures               :: { sink::SinkExt, StreamExt    } ,
+	pharos                :: { Observable, Filter, Channel } ,
+	web_sys               :: { WebSocket                   } ,
+	wasm_bindgen::prelude :: { *                           } ,
+	wasm_bindgen_test     :: { *                           } ,
+	ws_stream_wasm        :: { *                           } ,
+	log                   :: { *                           } ,
+	std::time             :: { Duration                    } ,
 };
 
 
 
-const URL: &str = "ws://127.0.0.1:3212/";
+const URL   : &str = "ws://127.0.0.1:3212/";
+const HANG  : &str = "ws://127.0.0.1:3313/";
+const SILENT: &str = "ws://127.0.0.1:3314/";
 
 
 
@@ -62,6 +89,7 @@ async fn connect_wrong_port()
 				was_clean: false,
 				code     : 1006 ,
 				reason   : "".to_string(),
+				initiator: CloseInitiator::Abnormal,
 			}
 		},
 
@@ -117,6 +145,7 @@ async fn connect_wrong_wss()
 				was_clean: false,
 				code     : 1006 ,
 				reason   : "".to_string(),
+				initiator: CloseInitiator::Abnormal,
 			}
 		},
 
@@ -136,13 +165,128 @@ async fn connect_wrong_scheme()
 
 	info!( "starting test: connect_wrong_scheme" );
 
-	let err = WsMeta::connect( "http://127.0.0.1:3212/", None ).await;
+	let err = WsMeta::connect( "ftp://127.0.0.1:3212/", None ).await;
 
 	assert!( err.is_err() );
 
 	let err = err.unwrap_err();
 
-	assert_eq!( WsErr::InvalidUrl{ supplied: "http://127.0.0.1:3212/".to_string() }, err );
+	assert_eq!
+	(
+		WsErr::InvalidUrl
+		{
+			supplied: "ftp://127.0.0.1:3212/".to_string(),
+			reason  : UrlError::UnsupportedScheme{ scheme: "ftp".to_string() },
+		},
+
+		err
+	);
+}
+
+
+
+// WsMeta::connect: Verify error when connecting to a url with a fragment
+//
+#[ wasm_bindgen_test ]
+//
+async fn connect_fragment()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connect_fragment" );
+
+	let err = WsMeta::connect( "ws://127.0.0.1:3212/#chat", None ).await.unwrap_err();
+
+	assert_eq!
+	(
+		WsErr::InvalidUrl{ supplied: "ws://127.0.0.1:3212/#chat".to_string(), reason: UrlError::Fragment },
+		err
+	);
+}
+
+
+
+// WsMeta::connect: Verify http urls and urls relative to the location of the test page are turned into ws urls
+//
+#[ wasm_bindgen_test ]
+//
+async fn connect_resolve_url()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connect_resolve_url" );
+
+	let (ws, _wsio) = WsMeta::connect( "http://127.0.0.1:3212/", None ).await.expect_throw( "connect http url" );
+
+	assert_eq!( "ws://127.0.0.1:3212/", ws.url() );
+
+	// The test page is served over http, so a scheme relative url becomes ws.
+	//
+	let (ws, _wsio) = WsMeta::connect( "//127.0.0.1:3212/", None ).await.expect_throw( "connect relative url" );
+
+	assert_eq!( "ws://127.0.0.1:3212/", ws.url() );
+}
+
+
+
+// WsMeta::connect: Verify error on an invalid or duplicate sub-protocol
+//
+#[ wasm_bindgen_test ]
+//
+async fn connect_invalid_protocol()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connect_invalid_protocol" );
+
+	let err = WsMeta::connect( URL, vec![ "not a token" ] ).await.unwrap_err();
+
+	assert_eq!( WsErr::InvalidProtocol{ supplied: "not a token".to_string() }, err );
+
+	let err = WsMeta::connect( URL, vec![ "chat", "chat" ] ).await.unwrap_err();
+
+	assert_eq!( WsErr::InvalidProtocol{ supplied: "chat".to_string() }, err );
+}
+
+
+
+// WsMeta::connect: Verify SecurityError when connecting to ws:// from a page served over https.
+//
+// The test runner serves the test page over http, where browsers allow the connection, so this only passes when
+// run with `--include-ignored` against a page served over https.
+//
+#[ wasm_bindgen_test ]
+#[ ignore = "needs the test page to be served over https" ]
+//
+async fn connect_mixed_content()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connect_mixed_content" );
+
+	// Localhost is considered secure, so use a remote host. The browser throws before making any connection.
+	//
+	let err = WsMeta::connect( "ws://example.com/", None ).await.unwrap_err();
+
+	assert!( matches!( err, WsErr::SecurityError{..} ), "unexpected error: {:?}", err );
+}
+
+
+
+// WsMeta::connect_timeout: Verify error when the connection isn't established in time. The server of
+// ci/test_server.py on this port accepts the TCP connection but never answers the handshake.
+//
+#[ wasm_bindgen_test ]
+//
+async fn connect_timeout()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connect_timeout" );
+
+	let err = WsMeta::connect_timeout( HANG, None, Duration::from_millis( 100 ) ).await;
+
+	assert_eq!( WsErr::ConnectTimeout, err.unwrap_err() );
 }
 
 
@@ -233,6 +377,111 @@ async fn no_protocols()
 
 
 
+// A typed sub-protocol.
+//
+#[ derive( Debug, Clone, Copy, PartialEq, Eq ) ]
+//
+enum Chat { V1, Invalid }
+
+impl SubProtocol for Chat
+{
+	fn name( &self ) -> &str
+	{
+		match self
+		{
+			Chat::V1      => "chat.v1"    ,
+			Chat::Invalid => "chat/broken",
+		}
+	}
+
+	fn from_name( name: &str ) -> Option<Self>
+	{
+		[ Chat::V1, Chat::Invalid ].iter().copied().find( |p| p.name() == name )
+	}
+}
+
+
+
+// WsConfig::require_protocol: Verify error when the server doesn't select a protocol, and when no protocol is
+// offered at all.
+//
+#[ wasm_bindgen_test ]
+//
+async fn require_protocol()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: require_protocol" );
+
+	let (ws, _wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	assert_eq!( None, ws.subprotocol::<Chat>() );
+
+	// The echo server never selects a protocol.
+	//
+	let err = WsMeta::builder( URL ).subprotocols( vec![ Chat::V1 ] ).require_protocol().connect().await.unwrap_err();
+
+	assert_eq!( WsErr::ProtocolNotNegotiated, err );
+
+	let err = WsMeta::builder( URL ).require_protocol().connect().await.unwrap_err();
+
+	assert_eq!( WsErr::NoProtocolOffered, err );
+}
+
+
+
+// WsConfig::observer_channel: Verify a bounded channel is used and a channel of size 0 is rejected.
+//
+#[ wasm_bindgen_test ]
+//
+async fn observer_channel()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: observer_channel" );
+
+	let err = WsMeta::builder( URL ).observer_channel( Channel::Bounded( 0 ) ).connect().await.unwrap_err();
+
+	assert_eq!( WsErr::InvalidObserverChannel, err );
+
+	let (ws, _wsio) = WsMeta::builder( URL ).observer_channel( Channel::Bounded( 1 ) ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut states = ws.state_watch();
+
+	assert_eq!( Some( WsState::Open ), states.next().await );
+
+	let evt = ws.close().await.expect_throw( "close" );
+
+	assert_eq!( Some( WsState::Closing ), states.next().await );
+	assert_eq!( Some( WsState::Closed  ), states.next().await );
+	assert_eq!( None                    , states.next().await );
+	assert_eq!( evt                     , ws.closed().await   );
+}
+
+
+
+// WsConfig::subprotocols: Verify typed protocols with duplicate or invalid names are rejected.
+//
+#[ wasm_bindgen_test ]
+//
+async fn subprotocols_invalid()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: subprotocols_invalid" );
+
+	let err = WsMeta::builder( URL ).subprotocols( vec![ Chat::V1, Chat::Invalid ] ).connect().await.unwrap_err();
+
+	assert_eq!( WsErr::InvalidProtocol{ supplied: "chat/broken".to_string() }, err );
+
+	let err = WsMeta::builder( URL ).subprotocols( vec![ Chat::V1, Chat::V1 ] ).connect().await.unwrap_err();
+
+	assert_eq!( WsErr::InvalidProtocol{ supplied: "chat.v1".to_string() }, err );
+}
+
 /*
 // Verify protocols.
 // This doesn't work with tungstenite for the moment.
@@ -310,6 +559,32 @@ async fn close_code_invalid()
 }
 
 
+
+// Verify closing with a typed close code, and that disallowed codes are rejected without closing.
+//
+#[ wasm_bindgen_test ]
+//
+async fn close_code_typed()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: close_code_typed" );
+
+	let (ws, _wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	let res = ws.close_code( CloseCode::Away ).await;
+
+	assert_eq!( WsErr::InvalidCloseCode{ supplied: 1001 }, res.unwrap_err() );
+	assert_eq!( WsState::Open, ws.ready_state() );
+
+	let evt = ws.close_code( CloseCode::Application( 4000 ) ).await.expect_throw( "close" );
+
+	assert_eq!( CloseCode::Application( 4000 ), evt.close_code() );
+	assert_eq!( CloseInitiator::Local          , evt.initiator    );
+	assert!( !evt.is_abnormal() );
+}
+
+
 // Verify close_code method.
 //
 #[ wasm_bindgen_test ]
@@ -382,3 +657,289 @@ async fn debug()
 	ws.close().await.expect_throw( "close" );
 }
 
+
+
+// WsMeta::builder: Verify error on an invalid drop close code.
+//
+#[ wasm_bindgen_test ]
+//
+async fn builder_invalid_drop_close_code()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: builder_invalid_drop_close_code" );
+
+	let err = WsMeta::builder( URL ).drop_close_code( 1001 ).connect().await;
+
+	assert_eq!( WsErr::InvalidCloseCode{ supplied: 1001 }, err.unwrap_err() );
+}
+
+
+
+// WsMeta::builder: Verify the drop close code is used when closing from WsStream.
+//
+#[ wasm_bindgen_test ]
+//
+async fn builder_drop_close_code()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: builder_drop_close_code" );
+
+	let (mut ws, mut wsio) = WsMeta::builder( URL ).drop_close_code( 4000 ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_closed ).into() ).await.expect( "observe" );
+
+	wsio.close().await.expect_throw( "close ws" );
+
+	match evts.next().await.unwrap_throw()
+	{
+		WsEvent::Closed( evt ) =>
+		{
+			assert_eq!( 4000                 , evt.code      );
+			assert_eq!( CloseInitiator::Local, evt.initiator );
+		}
+
+		evt => panic!( "unexpected event: {:?}", evt ),
+	}
+}
+
+
+
+// WsConfig::close_timeout: Verify close resolves with a synthesized close event when the handshake takes too long.
+// The server of ci/test_server.py on this port never answers the close frame.
+//
+#[ wasm_bindgen_test ]
+//
+async fn close_timeout()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: close_timeout" );
+
+	let (mut ws, mut wsio) = WsMeta::builder( SILENT ).close_timeout( Duration::from_millis( 100 ) ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_closed ).into() ).await.expect( "observe" );
+
+	let synthesized = CloseEvent
+	{
+		code     : 1006                  ,
+		reason   : String::new()         ,
+		was_clean: false                 ,
+		initiator: CloseInitiator::Local ,
+	};
+
+	assert_eq!( synthesized, ws.close().await.expect_throw( "close" ) );
+	assert_eq!( Some( WsEvent::Closed( synthesized ) ), evts.next().await );
+
+	// The stream ends even though the browser might not consider the connection closed yet.
+	//
+	assert_eq!( None, wsio.next().await );
+}
+
+
+// WsConfig::idle_timeout: Verify an idle connection is closed normally after an IdleTimeout event.
+//
+#[ wasm_bindgen_test ]
+//
+async fn idle_timeout()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: idle_timeout" );
+
+	let (mut ws, _wsio) = WsMeta::builder( URL ).idle_timeout( Duration::from_millis( 50 ), "idle" ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let filter   = Filter::Pointer( |evt: &WsEvent| evt.is_idle_timeout() || evt.is_closed() );
+	let mut evts = ws.observe( filter.into() ).await.expect( "observe" );
+
+	assert_eq!( Some( WsEvent::IdleTimeout ), evts.next().await );
+
+	match evts.next().await.unwrap_throw()
+	{
+		WsEvent::Closed( evt ) =>
+		{
+			assert!( evt.is_normal() );
+			assert_eq!( CloseInitiator::Local, evt.initiator );
+		}
+
+		evt => panic!( "unexpected event: {:?}", evt ),
+	}
+}
+
+
+
+// WsConfig::idle_timeout: Verify error on a reason string that is too long.
+//
+#[ wasm_bindgen_test ]
+//
+async fn idle_timeout_reason_too_long()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: idle_timeout_reason_too_long" );
+
+	let err = WsMeta::builder( URL ).idle_timeout( Duration::from_secs( 1 ), vec![ "a"; 124 ].join( "" ) ).connect().await;
+
+	assert_eq!( WsErr::ReasonStringToLong, err.unwrap_err() );
+}
+
+
+// WsMeta::from_websocket: Verify a connecting socket opens and can send.
+//
+#[ wasm_bindgen_test ]
+//
+async fn from_websocket_connecting()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: from_websocket_connecting" );
+
+	let raw                = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let (mut ws, mut wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+
+	assert_eq!( WsState::Connecting, ws.ready_state() );
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_open ).into() ).await.expect( "observe" );
+
+	// The sink waits for the connection to open.
+	//
+	wsio.send( WsMessage::Text( "hello".into() ) ).await.expect_throw( "send" );
+
+	assert_eq!( Some( WsEvent::Open ), evts.next().await );
+	assert_eq!( Some( WsMessage::Text( "hello".into() ) ), wsio.next().await );
+}
+
+
+
+// WsMeta::from_websocket: Verify an open socket reports Open and survives WsStream::into_inner.
+//
+#[ wasm_bindgen_test ]
+//
+async fn from_websocket_open()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: from_websocket_open" );
+
+	let (_ws, wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let raw         = wsio.into_inner();
+
+	assert_eq!( WebSocket::OPEN, raw.ready_state() );
+
+	let (mut ws, mut wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_open ).into() ).await.expect( "observe" );
+
+	assert_eq!( Some( WsEvent::Open ), evts.next().await );
+
+	// The stream gets it too, in front of the messages.
+	//
+	assert_eq!( Some( WsItem::Event( WsEvent::Open ) ), wsio.items().next().await );
+
+	wsio.send( WsMessage::Binary( vec![ 1, 2, 3 ] ) ).await.expect_throw( "send" );
+
+	assert_eq!( Some( WsMessage::Binary( vec![ 1, 2, 3 ] ) ), wsio.next().await );
+}
+
+
+
+// WsMeta::from_websocket: Verify error on a closing socket.
+//
+#[ wasm_bindgen_test ]
+//
+async fn from_websocket_closing()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: from_websocket_closing" );
+
+	let raw = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+
+	raw.close().expect_throw( "close raw websocket" );
+
+	assert_eq!( WsErr::ConnectionNotOpen, WsMeta::from_websocket( raw ).unwrap_err() );
+}
+
+
+
+// WsMeta::state_watch: Verify the current state is yielded first, followed by every transition.
+//
+#[ wasm_bindgen_test ]
+//
+async fn state_watch()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: state_watch" );
+
+	let (ws, _wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	let mut watch = ws.state_watch();
+
+	assert_eq!( Some( WsState::Open ), watch.next().await );
+
+	ws.close().await.expect_throw( "close" );
+
+	assert_eq!( Some( WsState::Closing ), watch.next().await );
+	assert_eq!( Some( WsState::Closed  ), watch.next().await );
+	assert_eq!( None                    , watch.next().await );
+
+	// A watch created after closing only yields Closed.
+	//
+	let mut late = ws.state_watch();
+
+	assert_eq!( Some( WsState::Closed ), late.next().await );
+	assert_eq!( None                   , late.next().await );
+}
+
+
+
+// WsMeta::state_watch: Verify a watch created on a connecting socket starts with Connecting.
+//
+#[ wasm_bindgen_test ]
+//
+async fn state_watch_connecting()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: state_watch_connecting" );
+
+	let raw         = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let (ws, _wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+
+	let mut watch = ws.state_watch();
+
+	assert_eq!( Some( WsState::Connecting ), watch.next().await );
+	assert_eq!( Some( WsState::Open       ), watch.next().await );
+}
+
+
+
+// WsMeta::closed: Verify it resolves with the close event, also when called after the connection closed.
+//
+#[ wasm_bindgen_test ]
+//
+async fn closed()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: closed" );
+
+	let (ws, _wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	let (evt, waited) = futures::join!( ws.close(), ws.closed() );
+	let evt           = evt.expect_throw( "close" );
+
+	assert_eq!( evt, waited            );
+	assert_eq!( evt, ws.closed().await );
+}
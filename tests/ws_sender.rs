@@ -0,0 +1,108 @@
+wasm_bindgen_test_configure!(run_in_browser);
+
+
+
+// What's tested:
+//
+// Tests send to an echo server which just bounces back all data.
+//
+// ✔ Verify WsSender is Send, Sync and Clone
+// ✔ Send from several clones and verify all messages come back in order
+// ✔ Verify try_send fails while connecting and send waits for the connection to open
+// ✔ Verify sending fails once the stream has been dropped
+//
+use
+{
+	futures::prelude      :: *           ,
+	log                   :: *           ,
+	std::marker           :: PhantomData ,
+	wasm_bindgen::prelude :: *           ,
+	wasm_bindgen_test     :: *           ,
+	web_sys               :: WebSocket   ,
+	ws_stream_wasm        :: *           ,
+};
+
+
+
+const URL: &str = "ws://127.0.0.1:3212/";
+
+
+
+// Verify WsSender is Send, Sync and Clone.
+//
+#[ wasm_bindgen_test ]
+//
+fn sendness()
+{
+	struct SendNess<T: Send + Sync + Clone>{ _phantom: PhantomData<T> }
+
+	let _x = SendNess::<WsSender>{ _phantom: PhantomData };
+}
+
+
+
+// Send from several clones and verify all messages come back in order.
+//
+#[ wasm_bindgen_test ]
+//
+async fn clones()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: clones" );
+
+	let (_ws, mut wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	let a = wsio.sender();
+	let b = a.clone();
+
+	a.send    ( WsMessage::Text( "a".to_string() ) ).await.expect_throw( "send a" );
+	b.try_send( WsMessage::Text( "b".to_string() ) )       .expect_throw( "send b" );
+
+	assert_eq!( Some( WsMessage::Text( "a".to_string() ) ), wsio.next().await );
+	assert_eq!( Some( WsMessage::Text( "b".to_string() ) ), wsio.next().await );
+}
+
+
+
+// Verify try_send fails while connecting and send waits for the connection to open.
+//
+#[ wasm_bindgen_test ]
+//
+async fn connecting()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: connecting" );
+
+	let raw             = WebSocket::new( URL ).expect_throw( "create raw websocket" );
+	let (_ws, mut wsio) = WsMeta::from_websocket( raw ).expect_throw( "wrap websocket" );
+	let sender          = wsio.sender();
+
+	assert_eq!( Err( WsErr::ConnectionNotOpen ), sender.try_send( WsMessage::Text( "hello".into() ) ) );
+
+	sender.send( WsMessage::Text( "hello".into() ) ).await.expect_throw( "send" );
+
+	assert_eq!( Some( WsMessage::Text( "hello".into() ) ), wsio.next().await );
+}
+
+
+
+// Verify sending fails once the stream has been dropped.
+//
+#[ wasm_bindgen_test ]
+//
+async fn dropped()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: dropped" );
+
+	let (_ws, wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let sender      = wsio.sender();
+
+	drop( wsio );
+
+	assert_eq!( WsState::Closed, sender.ready_state() );
+	assert_eq!( Err( WsErr::ConnectionNotOpen ), sender.send( WsMessage::Text( "hello".into() ) ).await );
+}
@@ -8,9 +8,19 @@ wasm_bindgen_test_configure!(run_in_browser);
 //
 // ✔ Send a WsMessage::Text   and verify we get an identical WsMessage back.
 // ✔ Send a WsMessage::Binary and verify we get an identical WsMessage back.
+// ✔ Receive a binary message through js_messages and verify the data without copying it all
+// ✔ With binary type Blob, verify binary messages are read in order of arrival
+// ✔ With binary type Blob, verify js_messages yields the blob itself
 // ✔ Send while closing and verify the error
 // ✔ Send while closed  and verify the error
 // ✔ Test Debug impl
+// ✔ With send watermarks, verify flush waits until the browser has transmitted everything
+// ✔ With a full receive queue and OverflowPolicy::Error, verify messages are dropped and reported, and repeated
+//   errors are coalesced in the receive queue
+// ✔ With a full receive queue and OverflowPolicy::Close, verify the connection is closed
+// ✔ Split, use the halves from different tasks and verify the round trip
+// ✔ Reunite halves of the same stream, and verify halves of different streams are given back
+// ✔ Drop the reader and verify the connection stays open until the writer is dropped
 //
 // Note that AsyncRead/AsyncWrite are tested by futures_codec.rs and tokio_codec.rs
 //
@@ -23,6 +33,7 @@ use
 	wasm_bindgen::prelude :: *           ,
 	wasm_bindgen_futures  :: spawn_local ,
 	wasm_bindgen_test     :: *           ,
+	web_sys               :: BinaryType  ,
 	ws_stream_wasm        :: *           ,
 };
 
@@ -47,6 +58,8 @@ fn sendness()
 
 	let _x = SendNess::<WsStream>{ _phantom: PhantomData };
 	let _x = SendNess::<WsMeta  >{ _phantom: PhantomData };
+	let _x = SendNess::<WsReader>{ _phantom: PhantomData };
+	let _x = SendNess::<WsWriter>{ _phantom: PhantomData };
 }
 
 
@@ -105,6 +118,102 @@ async fn round_trip_binary()
 
 
 
+// Receive a binary message through js_messages and verify the data without copying it all.
+//
+#[ wasm_bindgen_test ]
+//
+async fn round_trip_js_binary()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: round_trip_js_binary" );
+
+	let (_ws, mut wsio) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let message         = b"Hello from browser".to_vec();
+
+	wsio.send( WsMessage::Binary( message.clone() ) ).await.expect_throw( "Failed to write to websocket" );
+
+	let bytes = match wsio.js_messages().next().await.expect_throw( "Stream closed" )
+	{
+		JsMessage::Binary( bytes ) => bytes,
+		msg                        => panic!( "unexpected message: {:?}", msg ),
+	};
+
+	assert_eq!( message.len(), bytes.len() );
+	assert_eq!( message.len(), bytes.as_uint8_array().length() as usize );
+
+	let mut start = [ 0u8; 5 ];
+
+	assert_eq!( 5, bytes.copy_into( &mut start ) );
+	assert_eq!( b"Hello", &start );
+
+	assert_eq!( message, bytes.to_vec() );
+}
+
+
+
+// Verify that with binary type Blob, the Stream reads blobs and keeps the order of messages.
+//
+#[ wasm_bindgen_test ]
+//
+async fn round_trip_blob()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: round_trip_blob" );
+
+	let (_ws, mut wsio) = WsMeta::builder( URL )
+
+		.binary_type( BinaryType::Blob )
+		.connect().await
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let first  = vec![ 1u8; 100_000 ];
+	let second = b"Hello from browser".to_vec();
+
+	wsio.feed( WsMessage::Binary( first .clone() ) ).await.expect_throw( "Failed to write to websocket" );
+	wsio.send( WsMessage::Binary( second.clone() ) ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( WsMessage::Binary( first  ) ), wsio.next().await );
+	assert_eq!( Some( WsMessage::Binary( second ) ), wsio.next().await );
+}
+
+
+
+// Verify that with binary type Blob, js_messages exposes the blob.
+//
+#[ wasm_bindgen_test ]
+//
+async fn js_messages_blob()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: js_messages_blob" );
+
+	let (_ws, mut wsio) = WsMeta::builder( URL )
+
+		.binary_type( BinaryType::Blob )
+		.connect().await
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let message = b"Hello from browser".to_vec();
+
+	wsio.send( WsMessage::Binary( message.clone() ) ).await.expect_throw( "Failed to write to websocket" );
+
+	let blob = match wsio.js_messages().next().await.expect_throw( "Stream closed" )
+	{
+		JsMessage::Blob( blob ) => blob,
+		msg                     => panic!( "unexpected message: {:?}", msg ),
+	};
+
+	assert_eq!( message.len() as u64, blob.size() );
+	assert_eq!( message, blob.read().await.expect_throw( "read blob" ).to_vec() );
+}
+
+
+
 #[ wasm_bindgen_test ]
 //
 async fn send_while_closing()
@@ -240,3 +349,205 @@ async fn debug()
 
 	wsio.close().await.expect_throw( "close" );
 }
+
+
+
+// With send watermarks, verify flush waits until the browser has transmitted everything.
+//
+#[ wasm_bindgen_test ]
+//
+async fn backpressure_flush()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: backpressure_flush" );
+
+	let (ws, mut wsio) = WsMeta::builder( URL ).send_watermarks( 64 * 1024, 16 * 1024 ).connect().await
+
+		.expect_throw( "Could not create websocket" )
+	;
+
+	for _ in 0..16
+	{
+		wsio.feed( WsMessage::Binary( vec![ 0; 100 * 1024 ] ) ).await.expect_throw( "feed" );
+
+		// The high watermark is checked before accepting the next message.
+		//
+		assert!( ws.buffered_amount() <= 64 * 1024 + 100 * 1024 );
+	}
+
+	wsio.flush().await.expect_throw( "flush" );
+
+	assert_eq!( 0, ws.buffered_amount() );
+
+	wsio.close().await.expect_throw( "close" );
+}
+
+
+
+// With a full receive queue and OverflowPolicy::Error, verify messages are dropped and reported, and repeated
+// errors are coalesced in the receive queue.
+//
+#[ wasm_bindgen_test ]
+//
+async fn receive_queue_full_error()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: receive_queue_full_error" );
+
+	let (mut ws, mut wsio) = WsMeta::builder( URL )
+
+		.receive_capacity( 1                     )
+		.overflow_policy ( OverflowPolicy::Error )
+		.connect().await
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_ws_err ).into() ).await.expect( "observe" );
+
+	for i in 0..3u8
+	{
+		wsio.send( WsMessage::Binary( vec![ i ] ) ).await.expect_throw( "send" );
+	}
+
+	assert_eq!( WsEvent::WsErr( WsErr::ReceiveQueueFull ), evts.next().await.unwrap_throw() );
+	assert_eq!( WsEvent::WsErr( WsErr::ReceiveQueueFull ), evts.next().await.unwrap_throw() );
+
+	assert_eq!( 1, wsio.queue_len() );
+	assert_eq!( Some( WsItem::Message( WsMessage::Binary( vec![ 0 ] ) ) ), wsio.items().next().await );
+	assert_eq!( 0, wsio.queue_len() );
+
+	// The receive queue only holds one error for both dropped messages.
+	//
+	assert_eq!( Some( WsItem::Event( WsEvent::WsErr( WsErr::ReceiveQueueFull ) ) ), wsio.items().next().await );
+
+	wsio.send( WsMessage::Binary( vec![ 3 ] ) ).await.expect_throw( "send" );
+
+	assert_eq!( Some( WsItem::Message( WsMessage::Binary( vec![ 3 ] ) ) ), wsio.items().next().await );
+
+	wsio.close().await.expect_throw( "close" );
+}
+
+
+
+// With a full receive queue and OverflowPolicy::Close, verify the connection is closed.
+//
+#[ wasm_bindgen_test ]
+//
+async fn receive_queue_full_close()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: receive_queue_full_close" );
+
+	let (mut ws, mut wsio) = WsMeta::builder( URL )
+
+		.receive_capacity( 1                                                       )
+		.overflow_policy ( OverflowPolicy::Close( CloseCode::Application( 4000 ) ) )
+		.connect().await
+		.expect_throw( "Could not create websocket" )
+	;
+
+	let mut evts = ws.observe( Filter::Pointer( WsEvent::is_closed ).into() ).await.expect( "observe" );
+
+	wsio.send( WsMessage::Binary( vec![ 0 ] ) ).await.expect_throw( "send" );
+	wsio.send( WsMessage::Binary( vec![ 1 ] ) ).await.expect_throw( "send" );
+
+	match evts.next().await.unwrap_throw()
+	{
+		WsEvent::Closed( evt ) => assert_eq!( CloseCode::Application( 4000 ), evt.close_code() ),
+		evt                    => panic!( "unexpected event: {:?}", evt ),
+	}
+
+	assert_eq!( Some( WsMessage::Binary( vec![ 0 ] ) ), wsio.next().await );
+	assert_eq!( None, wsio.next().await );
+}
+
+
+
+// Split, use the halves from different tasks and verify the round trip.
+//
+#[ wasm_bindgen_test ]
+//
+async fn split_round_trip()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: split_round_trip" );
+
+	let (_ws, wsio)              = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let (mut reader, mut writer) = wsio.split();
+	let message                  = WsMessage::Text( "Hello from browser".to_string() );
+	let (tx, rx)                 = futures::channel::oneshot::channel();
+
+	spawn_local( async move
+	{
+		let _ = tx.send( reader.next().await );
+	});
+
+	writer.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), rx.await.expect_throw( "reader task" ) );
+}
+
+
+
+// Reunite halves of the same stream, and verify halves of different streams are given back.
+//
+#[ wasm_bindgen_test ]
+//
+async fn reunite()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: reunite" );
+
+	let (_ws , wsio ) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let (_ws2, wsio2) = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+
+	let (reader , writer ) = wsio .split();
+	let (reader2, writer2) = wsio2.split();
+
+	let ReuniteError( reader, writer2 ) = reader.reunite( writer2 ).expect_err( "halves of different streams" );
+
+	let mut wsio  = reader .reunite( writer  ).expect_throw( "reunite" );
+	let     wsio2 = reader2.reunite( writer2 ).expect_throw( "reunite" );
+
+	assert_eq!( WsState::Open, wsio2.ready_state() );
+
+	let message = WsMessage::Text( "Hello from browser".to_string() );
+
+	wsio.send( message.clone() ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( Some( message ), wsio.next().await );
+}
+
+
+
+// Drop the reader and verify the connection stays open until the writer is dropped.
+//
+#[ wasm_bindgen_test ]
+//
+async fn drop_reader()
+{
+	let _ = console_log::init_with_level( Level::Trace );
+
+	info!( "starting test: drop_reader" );
+
+	let (mut ws, wsio)       = WsMeta::connect( URL, None ).await.expect_throw( "Could not create websocket" );
+	let (reader, mut writer) = wsio.split();
+	let mut evts             = ws.observe( Filter::Pointer( WsEvent::is_closed ).into() ).await.expect( "observe" );
+
+	drop( reader );
+
+	// The echo is discarded.
+	//
+	writer.send( WsMessage::Text( "Hello from browser".to_string() ) ).await.expect_throw( "Failed to write to websocket" );
+
+	assert_eq!( WsState::Open, writer.ready_state() );
+
+	drop( writer );
+
+	assert!( evts.next().await.unwrap_throw().is_closed() );
+}